@@ -0,0 +1,105 @@
+use crate::{
+    persistence::{Alias, Database},
+    table,
+};
+
+/// Add (or replace) an alias for `users` or `schedules`, given by email, name, ID or another alias.
+pub fn add(db: &mut Database<'_>, name: &str, users: &[String], schedules: &[String]) {
+    let name = name.trim_start_matches('@');
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        eprintln!("an alias can't be empty or contain spaces");
+        std::process::exit(1);
+    }
+
+    let alias = match (users.is_empty(), schedules.is_empty()) {
+        (false, true) => Alias::Users(resolve(db, users, "user", |db, u| {
+            db.find_users(u).iter().map(|u| u.id.clone()).collect()
+        })),
+        (true, false) => Alias::Schedules(resolve(db, schedules, "schedule", |db, s| {
+            db.find_schedules(s).iter().map(|s| s.id.clone()).collect()
+        })),
+        _ => {
+            eprintln!("an alias is either for users (--user) or for schedules (--schedule)");
+            std::process::exit(1);
+        }
+    };
+
+    let replaced = db.aliases.insert(name.to_string(), alias).is_some();
+    db.save_aliases().expect("could not save aliases");
+
+    let verb = if replaced { "updated" } else { "added" };
+    println!("{verb} alias {name}, use it as {name} or @{name}");
+}
+
+/// The IDs of everything `names` refer to. A name has to refer to exactly one thing, unless it is
+/// an alias itself.
+fn resolve(
+    db: &Database<'_>,
+    names: &[String],
+    kind: &str,
+    find: impl Fn(&Database<'_>, &str) -> Vec<String>,
+) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    for name in names {
+        let found = find(db, name);
+        let is_alias = db.aliases.contains_key(name.trim_start_matches('@'));
+
+        match found.len() {
+            0 => {
+                eprintln!("no {kind} is called {name}");
+                std::process::exit(1);
+            }
+            1 => {}
+            n if !is_alias => {
+                eprintln!("{name} matches {n} {kind}s, use the ID of the one you mean");
+                std::process::exit(1);
+            }
+            _ => {}
+        }
+
+        for id in found {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    ids
+}
+
+pub fn remove(db: &mut Database<'_>, name: &str) {
+    let name = name.trim_start_matches('@');
+    if db.aliases.remove(name).is_none() {
+        eprintln!("there is no alias {name}");
+        std::process::exit(1);
+    }
+
+    db.save_aliases().expect("could not save aliases");
+    println!("removed alias {name}");
+}
+
+pub fn list(db: &Database<'_>) {
+    let rows: Vec<Vec<String>> = db
+        .aliases
+        .iter()
+        .map(|(name, alias)| {
+            let (kind, members) = match alias {
+                Alias::Users(_) => (
+                    "users",
+                    db.find_users(name)
+                        .iter()
+                        .map(|u| u.label())
+                        .collect::<Vec<_>>(),
+                ),
+                Alias::Schedules(_) => (
+                    "schedules",
+                    db.find_schedules(name).iter().map(|s| s.label()).collect(),
+                ),
+            };
+
+            vec![name.clone(), kind.to_string(), members.join(", ")]
+        })
+        .collect();
+
+    table::print(&["alias", "for", "members"], &rows);
+}
@@ -1,10 +1,21 @@
-use chrono::{DateTime, TimeZone};
+use chrono::{DateTime, FixedOffset, TimeZone};
 use indicatif::ProgressBar;
-use reqwest::RequestBuilder;
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    Certificate, Method, RequestBuilder, Response, StatusCode,
+};
 use serde::{Deserialize, Serialize};
 use std::{
     fmt::{Display, Write},
-    io, vec,
+    path::PathBuf,
+    time::Duration,
+};
+use thiserror::Error;
+
+use crate::{
+    credentials::{self, Source},
+    profile::{AuthKind, Profile, Region},
+    prompt::Prompt,
 };
 
 #[derive(Deserialize, Debug)]
@@ -22,10 +33,69 @@ struct MeResponse {
     user: User,
 }
 
-#[derive(Deserialize, Debug, Clone, Serialize)]
+// Everything but the id and email is optional, caches from older versions only have those two.
+#[derive(Deserialize, Debug, Clone, Serialize, Default)]
 pub struct User {
     pub id: String,
     pub email: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub html_url: String,
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub job_title: Option<String>,
+    #[serde(default)]
+    pub teams: Vec<Reference>,
+}
+
+impl User {
+    /// How the user is offered when picking one, with their name and teams to search by.
+    pub fn label(&self) -> String {
+        let mut label = self.email.clone();
+        if !self.name.is_empty() {
+            label.push_str(" - ");
+            label.push_str(&self.name);
+        }
+
+        label + &teams_label(&self.teams)
+    }
+}
+
+fn teams_label(teams: &[Reference]) -> String {
+    if teams.is_empty() {
+        return String::new();
+    }
+
+    let names: Vec<&str> = teams.iter().map(|t| t.summary.as_str()).collect();
+    format!(" [{}]", names.join(", "))
+}
+
+/// Who an API key belongs to, shown before the key is saved.
+#[derive(Debug)]
+pub struct KeyOwner {
+    /// The account's subdomain, as in `<account>.pagerduty.com`.
+    pub account: String,
+    /// The key's user, account level API keys have none.
+    pub user: Option<User>,
+}
+
+impl Display for KeyOwner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.user {
+            Some(user) => write!(f, "{user} in the {} account", self.account),
+            None => write!(f, "the {} account (account level key)", self.account),
+        }
+    }
+}
+
+/// The subdomain of a pagerduty web URL like `https://acme.pagerduty.com/users/P123`.
+fn account_of(html_url: &str) -> Option<String> {
+    let host = reqwest::Url::parse(html_url).ok()?.host_str()?.to_string();
+    host.split('.').next().map(String::from)
 }
 
 impl Display for User {
@@ -48,11 +118,30 @@ pub struct SchedulesResponse {
     pub total: u64,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-#[allow(dead_code)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct Schedule {
     pub id: String,
     pub name: String,
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub teams: Vec<Reference>,
+    #[serde(default)]
+    pub escalation_policies: Vec<Reference>,
+}
+
+impl Schedule {
+    /// How the schedule is offered when picking one, with its teams and time zone.
+    pub fn label(&self) -> String {
+        let mut label = self.name.clone() + &teams_label(&self.teams);
+        if let Some(tz) = &self.time_zone {
+            label.push_str(&format!(" ({tz})"));
+        }
+
+        label
+    }
 }
 
 impl Display for Schedule {
@@ -64,74 +153,584 @@ impl Display for Schedule {
     }
 }
 
+#[derive(Deserialize, Debug)]
+struct TeamsResponse {
+    teams: Vec<Team>,
+    more: bool,
+    limit: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Team {
+    pub id: String,
+    pub name: String,
+}
+
+/// A reference to another pagerduty object, as embedded in API responses.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Reference {
+    pub id: String,
+    #[serde(default)]
+    pub summary: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct ScheduleResponse {
+    schedule: RenderedSchedule,
+}
+
+#[derive(Deserialize, Debug)]
+struct RenderedSchedule {
+    final_schedule: SubSchedule,
+}
+
+#[derive(Deserialize, Debug)]
+struct SubSchedule {
+    rendered_schedule_entries: Vec<ScheduleEntry>,
+}
+
+/// A single shift from the final (rendered) layer of a schedule.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ScheduleEntry {
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    pub user: Reference,
+}
+
+#[derive(Deserialize, Debug)]
+struct OncallsResponse {
+    oncalls: Vec<Oncall>,
+    more: bool,
+    limit: i32,
+}
+
+/// One on-call period of a user on an escalation policy. `schedule` is missing when the user is on
+/// the escalation policy directly, and `start`/`end` are missing for permanent on-call.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Oncall {
+    pub user: Reference,
+    pub schedule: Option<Reference>,
+    pub escalation_policy: Reference,
+    pub escalation_level: u32,
+    pub start: Option<DateTime<FixedOffset>>,
+    pub end: Option<DateTime<FixedOffset>>,
+}
+
 #[derive(Debug, Serialize)]
 struct ScheduleOverrideRequest {
     overrides: Vec<ScheduleOverride>,
 }
 
+/// What became of a change. In a dry run the change is printed instead of sent, so there is no
+/// response to report.
+#[derive(Debug)]
+pub enum Sent<T> {
+    Done(T),
+    DryRun,
+}
+
 #[derive(Debug, Serialize)]
-struct ScheduleOverride {
+pub struct ScheduleOverride {
     start: String,
     end: String,
     user: UserRef,
 }
 
+impl ScheduleOverride {
+    pub fn new<Tz, O>(u: &User, from: DateTime<Tz>, to: DateTime<Tz>) -> ScheduleOverride
+    where
+        Tz: TimeZone<Offset = O>,
+        O: Display,
+    {
+        Self::for_user_id(&u.id, from, to)
+    }
+
+    /// An override for the user with `user_id`, for when there is only a reference to them.
+    pub fn for_user_id<Tz, O>(
+        user_id: &str,
+        from: DateTime<Tz>,
+        to: DateTime<Tz>,
+    ) -> ScheduleOverride
+    where
+        Tz: TimeZone<Offset = O>,
+        O: Display,
+    {
+        ScheduleOverride {
+            start: from.to_rfc3339(),
+            end: to.to_rfc3339(),
+            user: UserRef {
+                id: user_id.to_string(),
+                r#type: "user_reference".to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Override {
+    pub id: String,
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    pub user: Reference,
+}
+
+#[derive(Deserialize, Debug)]
+struct OverridesResponse {
+    overrides: Vec<Override>,
+}
+
+/// The outcome of one override in a (possibly multi-override) create request. Pagerduty
+/// reports success or failure per override, so a request can partially succeed.
+#[derive(Deserialize, Debug)]
+pub struct OverrideResult {
+    pub status: u16,
+    #[serde(rename = "override")]
+    pub created: Option<Override>,
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
 #[derive(Debug, Serialize)]
 struct UserRef {
     id: String,
     r#type: String,
 }
 
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    #[error("rate limited by pagerduty, try again in {0}s")]
+    RateLimited(u64),
+
+    #[error("pagerduty returned {status}: {message}")]
+    Api { status: StatusCode, message: String },
+
+    #[error("could not find the email to make changes as ({0}), set one with `profile set --from <email>`")]
+    MissingFrom(String),
+
+    #[error("pagerduty did not create the override: {0}")]
+    Rejected(String),
+}
+
+pub type ClientResult<T> = std::result::Result<T, ClientError>;
+
+#[derive(Deserialize, Debug)]
+struct ErrorResponse {
+    error: ApiError,
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiError {
+    message: String,
+    #[serde(default)]
+    errors: Vec<String>,
+}
+
+const MAX_ATTEMPTS: u32 = 5;
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Pagerduty's rate limits reset at the latest a minute in, longer waits asked for are cut short.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// How long to wait before retrying after `attempt` failed attempts. Pagerduty's `Retry-After` or
+/// `ratelimit-reset` headers win, up to `MAX_RATE_LIMIT_WAIT`, otherwise the backoff grows
+/// exponentially. `jitter` (in 0..1) spreads out retries so the concurrent user and schedule loads
+/// don't retry in lockstep.
+fn retry_delay(headers: &HeaderMap, attempt: u32, jitter: f64) -> Duration {
+    let header_secs = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+    };
+
+    if let Some(secs) = header_secs("retry-after").or_else(|| header_secs("ratelimit-reset")) {
+        return Duration::from_secs(secs).min(MAX_RATE_LIMIT_WAIT);
+    }
+
+    let backoff = BASE_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_BACKOFF);
+    backoff.mul_f64(0.5 + jitter / 2.0)
+}
+
+/// Network settings for talking to pagerduty.
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    pub connect_timeout: Duration,
+    pub timeout: Duration,
+    /// Send every request through this proxy. Without it the usual `HTTPS_PROXY`/`NO_PROXY`
+    /// environment variables apply.
+    pub proxy: Option<String>,
+    /// PEM files with extra root certificates to trust, e.g. for a TLS intercepting proxy.
+    pub ca_certs: Vec<PathBuf>,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        HttpOptions {
+            connect_timeout: Duration::from_secs(10),
+            timeout: Duration::from_secs(60),
+            proxy: None,
+            ca_certs: Vec::new(),
+        }
+    }
+}
+
+impl HttpOptions {
+    /// These options, overridden by `PD_QUICK_OVERRIDE_PROXY`, `PD_QUICK_OVERRIDE_CA_CERTS` (a
+    /// list of paths like `PATH`) and `PD_QUICK_OVERRIDE_TIMEOUT` (in seconds).
+    pub fn with_env(self) -> HttpOptions {
+        let mut options = self;
+        if let Ok(proxy) = std::env::var("PD_QUICK_OVERRIDE_PROXY") {
+            options.proxy = Some(proxy);
+        }
+        if let Some(paths) = std::env::var_os("PD_QUICK_OVERRIDE_CA_CERTS") {
+            options.ca_certs = std::env::split_paths(&paths).collect();
+        }
+        if let Some(secs) = std::env::var("PD_QUICK_OVERRIDE_TIMEOUT")
+            .ok()
+            .and_then(|t| t.parse().ok())
+        {
+            options.timeout = Duration::from_secs(secs);
+        }
+
+        options
+    }
+
+    pub fn build(&self) -> std::result::Result<reqwest::Client, Box<dyn std::error::Error>> {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.timeout);
+
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+
+        for path in &self.ca_certs {
+            let bundle = std::fs::read_to_string(path)
+                .map_err(|e| format!("could not read {}: {e}", path.display()))?;
+            for pem in split_pem_bundle(&bundle) {
+                builder = builder.add_root_certificate(Certificate::from_pem(pem.as_bytes())?);
+            }
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+/// Split a PEM file into its certificates, reqwest only reads the first one of a bundle.
+fn split_pem_bundle(bundle: &str) -> Vec<String> {
+    const END: &str = "-----END CERTIFICATE-----";
+
+    bundle
+        .split_inclusive(END)
+        .filter(|pem| pem.contains(END))
+        .map(|pem| pem.trim().to_string())
+        .collect()
+}
+
+/// The read scopes every command needs, with an endpoint that requires each of them. Scoped tokens
+/// also need `schedules.write`, which can't be checked without making a change.
+const REQUIRED_SCOPES: [(&str, &str); 3] = [
+    ("/users", "users.read"),
+    ("/schedules", "schedules.read"),
+    ("/oncalls", "oncalls.read"),
+];
+
 pub struct Client {
     api_key: String,
+    base_url: String,
+    profile: Profile,
+    http: reqwest::Client,
+    from: tokio::sync::OnceCell<String>,
+    account: tokio::sync::OnceCell<String>,
+    source: Source,
+    /// Print changes instead of sending them.
+    dry_run: bool,
 }
 
 impl Client {
-    pub fn new() -> std::result::Result<Client, Box<dyn std::error::Error>> {
-        let api_key = Self::get_api_key()?;
+    /// Open a client with the profile's API key, see `credentials` for where it is looked for.
+    /// Requests go to the API of the profile's service region, unless `PAGERDUTY_API_URL` points
+    /// somewhere else.
+    pub async fn new(
+        mut profile: Profile,
+        http: &HttpOptions,
+        prompt: &mut impl Prompt,
+    ) -> std::result::Result<Client, Box<dyn std::error::Error>> {
+        let http = http.build()?;
+        let (api_key, source) = Self::get_api_key(&http, &mut profile, prompt).await?;
+        let base_url = std::env::var("PAGERDUTY_API_URL")
+            .unwrap_or_else(|_| profile.region.base_url().to_string());
+
+        Ok(Client {
+            source,
+            ..Self::with_base_url(api_key, base_url)
+                .with_profile(profile)
+                .with_http(http)
+        })
+    }
+
+    pub fn with_base_url(api_key: String, base_url: String) -> Client {
+        Client {
+            api_key,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            profile: Profile::default(),
+            http: HttpOptions::default()
+                .build()
+                .expect("could not set up http client"),
+            from: tokio::sync::OnceCell::new(),
+            account: tokio::sync::OnceCell::new(),
+            source: Source::Keyring,
+            dry_run: false,
+        }
+    }
+
+    pub fn with_http(self, http: reqwest::Client) -> Client {
+        Client { http, ..self }
+    }
 
-        Ok(Client { api_key })
+    pub fn with_profile(self, profile: Profile) -> Client {
+        Client { profile, ..self }
     }
 
-    fn get_api_key() -> std::result::Result<String, Box<dyn std::error::Error>> {
-        let keyring_entry = keyring::Entry::new("pd-fast-override", "api-key");
-        match keyring_entry.get_password() {
-            Ok(secret) => Ok(secret),
-            Err(keyring::Error::NoEntry) => {
-                println!("no entry found in keyring, enter an API key");
+    pub fn with_dry_run(self, dry_run: bool) -> Client {
+        Client { dry_run, ..self }
+    }
+
+    /// Whether changes are only printed, see `with_dry_run`.
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
 
-                let mut prompt = String::new();
-                io::stdin().read_line(&mut prompt)?;
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// Where the API key of this client came from.
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
 
-                keyring_entry.set_password(prompt.trim())?;
+    /// The API key from `credentials`, or one entered now. An entered key is only saved, along with
+    /// the region and kind of key it settles in `profile`, once `prompt` confirms it.
+    async fn get_api_key(
+        http: &reqwest::Client,
+        profile: &mut Profile,
+        prompt: &mut impl Prompt,
+    ) -> std::result::Result<(String, Source), Box<dyn std::error::Error>> {
+        match credentials::find(profile)? {
+            Some(credential) => Ok((credential.secret, credential.source)),
+            None => {
+                println!(
+                    "no API key found for profile {}, enter one (it won't be shown)",
+                    profile.name
+                );
 
-                // TODO: avoid clone
-                Ok(String::from(prompt.trim()))
+                loop {
+                    let api_key = rpassword::prompt_password("API key: ")?;
+                    let api_key = api_key.trim();
+
+                    if api_key.is_empty() {
+                        println!("no API key was entered");
+                        continue;
+                    }
+
+                    let mut set_up = profile.clone();
+                    match Self::set_up_key(http, api_key, &mut set_up).await {
+                        Ok(owner) => {
+                            println!("this API key belongs to {owner}, save it?");
+                            if prompt.confirm() {
+                                if owner.user.is_none() && set_up.from_email.is_none() {
+                                    // there is no user to make changes as, see sender_email
+                                    let from = prompt.ask(
+                                        "changes made with an account level key have to name who \
+                                         makes them, enter your email",
+                                    );
+                                    set_up.from_email = Some(from);
+                                }
+                                *profile = set_up;
+                                profile.save()?;
+                                profile.keyring_entry().set_password(api_key)?;
+
+                                return Ok((String::from(api_key), Source::Keyring));
+                            }
+                        }
+                        Err(e) => println!("this API key can't be used: {e}"),
+                    }
+
+                    println!("enter another API key, or press ctrl-c to give up");
+                }
             }
-            Err(e) => Err(Box::from(e)),
         }
     }
 
-    pub fn clear_api_key() -> std::result::Result<(), Box<dyn std::error::Error>> {
-        let keyring_entry = keyring::Entry::new("pd-fast-override", "api-key");
-        return keyring_entry.delete_password().map_err(|x| Box::from(x));
+    /// Check a newly entered API key works, settling the profile's region and kind of key along
+    /// the way, and find out who it belongs to.
+    async fn set_up_key(
+        http: &reqwest::Client,
+        api_key: &str,
+        profile: &mut Profile,
+    ) -> std::result::Result<KeyOwner, Box<dyn std::error::Error>> {
+        let base_url = match std::env::var("PAGERDUTY_API_URL") {
+            Ok(base_url) => base_url,
+            Err(_) => {
+                let (region, auth) = Self::detect_account(http, api_key, profile).await?;
+                if region != profile.region {
+                    println!("this API key belongs to an account in the {region} service region");
+                }
+                if auth != profile.auth {
+                    println!("this API key is an {auth}");
+                }
+                profile.region = region;
+                profile.auth = auth;
+                region.base_url().to_string()
+            }
+        };
+
+        let client = Client::with_base_url(api_key.to_string(), base_url)
+            .with_profile(profile.clone())
+            .with_http(http.clone());
+        client.check_scopes().await?;
+
+        Ok(client.key_owner().await?)
     }
 
-    pub async fn get_users(&self, pb: ProgressBar) -> reqwest::Result<Vec<User>> {
-        let client = reqwest::Client::new();
+    /// The subdomain of the account the API key belongs to, looked up once.
+    pub async fn account(&self) -> ClientResult<&str> {
+        let account = self
+            .account
+            .get_or_try_init(|| async { Ok::<_, ClientError>(self.key_owner().await?.account) })
+            .await?;
 
+        Ok(account)
+    }
+
+    /// Who the API key belongs to. Account level API keys have no user, so the account is taken
+    /// from any user instead.
+    async fn key_owner(&self) -> ClientResult<KeyOwner> {
+        let user = match self.get_me().await {
+            Ok(me) => Some(me),
+            Err(ClientError::Api {
+                status: StatusCode::BAD_REQUEST,
+                ..
+            }) => None,
+            Err(e) => return Err(e),
+        };
+
+        let html_url = match &user {
+            Some(me) => me.html_url.clone(),
+            None => {
+                let req = self.http.get(self.url("/users")).query(&[("limit", 1)]);
+                let users = self
+                    .execute(req, None)
+                    .await?
+                    .json::<UserResponse>()
+                    .await?;
+                users
+                    .users
+                    .first()
+                    .map(|u| u.html_url.clone())
+                    .unwrap_or_default()
+            }
+        };
+
+        Ok(KeyOwner {
+            account: account_of(&html_url).unwrap_or_else(|| String::from("unknown")),
+            user,
+        })
+    }
+
+    /// Find the service region an API key works in and whether it is a REST API key or an OAuth
+    /// token, trying the profile's settings first. Both a key of the other region and a key sent
+    /// with the wrong scheme are rejected with a 401, a 403 means the key works but lacks a scope.
+    async fn detect_account(
+        http: &reqwest::Client,
+        api_key: &str,
+        profile: &Profile,
+    ) -> std::result::Result<(Region, AuthKind), Box<dyn std::error::Error>> {
+        for region in [profile.region, profile.region.other()] {
+            for auth in [profile.auth, profile.auth.other()] {
+                let client =
+                    Client::with_base_url(api_key.to_string(), region.base_url().to_string())
+                        .with_profile(Profile {
+                            auth,
+                            ..profile.clone()
+                        })
+                        .with_http(http.clone());
+                let req = client.http.get(client.url("/users")).query(&[("limit", 1)]);
+
+                match client.execute(req, None).await {
+                    Err(ClientError::Api {
+                        status: StatusCode::UNAUTHORIZED,
+                        ..
+                    }) => continue,
+                    Err(ClientError::Api {
+                        status: StatusCode::FORBIDDEN,
+                        ..
+                    })
+                    | Ok(_) => return Ok((region, auth)),
+                    Err(e) => return Err(Box::from(e)),
+                }
+            }
+        }
+
+        Err(Box::from(
+            "the API key was rejected in both the US and EU service regions",
+        ))
+    }
+
+    /// Check that the key can read everything the commands need, scoped OAuth tokens are turned
+    /// away with a 403 from endpoints outside their scopes.
+    async fn check_scopes(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let mut missing = Vec::new();
+        for (path, scope) in REQUIRED_SCOPES {
+            let req = self.http.get(self.url(path)).query(&[("limit", 1)]);
+
+            match self.execute(req, None).await {
+                Err(ClientError::Api {
+                    status: StatusCode::FORBIDDEN,
+                    ..
+                }) => missing.push(scope),
+                Err(e) => return Err(Box::from(e)),
+                Ok(_) => {}
+            }
+        }
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        Err(Box::from(format!(
+            "this token is missing the {} scope(s). Grant the app {} and schedules.write in \
+             pagerduty, then enter the new token",
+            missing.join(", "),
+            REQUIRED_SCOPES.map(|(_, scope)| scope).join(", "),
+        )))
+    }
+
+    pub fn clear_api_key(profile: &Profile) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let keyring_entry = profile.keyring_entry();
+        keyring_entry.delete_password().map_err(Box::from)
+    }
+
+    pub async fn get_users(&self, pb: ProgressBar) -> ClientResult<Vec<User>> {
         let mut offset = 0;
 
         let page_size = 100;
 
         let mut all_users = Vec::new();
         loop {
-            let req = client
-                .get("https://api.pagerduty.com/users")
+            let req = self
+                .http
+                .get(self.url("/users"))
                 .query(&[("offset", offset), ("limit", page_size)])
                 .query(&[("total", true)]);
 
-            let resp = self.add_common_headers(req).send().await?;
+            let resp = self.execute(req, Some(&pb)).await?;
             let users = resp.json::<UserResponse>().await?;
 
             pb.set_length(users.total);
@@ -149,19 +748,18 @@ impl Client {
         }
     }
 
-    pub async fn get_schedules(&self, pb: ProgressBar) -> reqwest::Result<Vec<Schedule>> {
-        let client = reqwest::Client::new();
-
+    pub async fn get_schedules(&self, pb: ProgressBar) -> ClientResult<Vec<Schedule>> {
         let mut all_schedules = Vec::new();
         let mut offset = 0;
         let page_size = 100;
         loop {
-            let req = client
-                .get("https://api.pagerduty.com/schedules")
+            let req = self
+                .http
+                .get(self.url("/schedules"))
                 .query(&[("offset", offset), ("limit", page_size)])
                 .query(&[("total", true)]);
 
-            let resp = self.add_common_headers(req).send().await?;
+            let resp = self.execute(req, Some(&pb)).await?;
             let schedules = resp.json::<SchedulesResponse>().await?;
 
             pb.set_length(schedules.total);
@@ -177,55 +775,674 @@ impl Client {
         }
     }
 
-    pub async fn get_me(&self) -> reqwest::Result<User> {
-        let client = reqwest::Client::new();
-        let req = client.get("https://api.pagerduty.com/users/me");
-        let resp = self.add_common_headers(req).send().await?;
+    pub async fn get_teams(&self) -> ClientResult<Vec<Team>> {
+        let mut all_teams = Vec::new();
+        let mut offset = 0;
+        let page_size = 100;
+        loop {
+            let req = self
+                .http
+                .get(self.url("/teams"))
+                .query(&[("offset", offset), ("limit", page_size)]);
+
+            let resp = self.execute(req, None).await?;
+            let teams = resp.json::<TeamsResponse>().await?;
+
+            offset += teams.limit;
+            all_teams.extend(teams.teams);
+
+            if !teams.more {
+                return Ok(all_teams);
+            }
+        }
+    }
+
+    pub async fn get_me(&self) -> ClientResult<User> {
+        let req = self.http.get(self.url("/users/me"));
+        let resp = self.execute(req, None).await?;
         let user = resp.json::<MeResponse>().await?;
 
         Ok(user.user)
     }
 
+    /// Fetch the final, rendered entries of a schedule between `since` and `until`.
+    pub async fn get_schedule_entries<Tz, O>(
+        &self,
+        s: &Schedule,
+        since: DateTime<Tz>,
+        until: DateTime<Tz>,
+    ) -> ClientResult<Vec<ScheduleEntry>>
+    where
+        Tz: TimeZone<Offset = O>,
+        O: Display,
+    {
+        let req = self
+            .http
+            .get(self.url(&format!("/schedules/{}", s.id)))
+            .query(&[("since", since.to_rfc3339()), ("until", until.to_rfc3339())]);
+
+        let resp = self.execute(req, None).await?;
+        let schedule = resp.json::<ScheduleResponse>().await?;
+
+        Ok(schedule.schedule.final_schedule.rendered_schedule_entries)
+    }
+
+    /// Fetch every on-call period overlapping `since`..`until`, optionally restricted to some users
+    /// or schedules.
+    pub async fn get_oncalls<Tz, O>(
+        &self,
+        since: DateTime<Tz>,
+        until: DateTime<Tz>,
+        user_ids: &[&str],
+        schedule_ids: &[&str],
+    ) -> ClientResult<Vec<Oncall>>
+    where
+        Tz: TimeZone<Offset = O>,
+        O: Display,
+    {
+        let mut all_oncalls = Vec::new();
+        let mut offset = 0;
+        let page_size = 100;
+        loop {
+            let mut req = self
+                .http
+                .get(self.url("/oncalls"))
+                .query(&[("offset", offset), ("limit", page_size)])
+                .query(&[("since", since.to_rfc3339()), ("until", until.to_rfc3339())]);
+            for id in user_ids {
+                req = req.query(&[("user_ids[]", id)]);
+            }
+            for id in schedule_ids {
+                req = req.query(&[("schedule_ids[]", id)]);
+            }
+
+            let resp = self.execute(req, None).await?;
+            let oncalls = resp.json::<OncallsResponse>().await?;
+
+            offset += oncalls.limit;
+            all_oncalls.extend(oncalls.oncalls);
+
+            if !oncalls.more {
+                return Ok(all_oncalls);
+            }
+        }
+    }
+
+    /// Fetch the overrides of a schedule overlapping `since`..`until`.
+    pub async fn get_schedule_overrides<Tz, O>(
+        &self,
+        s: &Schedule,
+        since: DateTime<Tz>,
+        until: DateTime<Tz>,
+    ) -> ClientResult<Vec<Override>>
+    where
+        Tz: TimeZone<Offset = O>,
+        O: Display,
+    {
+        let req = self
+            .http
+            .get(self.url(&format!("/schedules/{}/overrides", s.id)))
+            .query(&[("since", since.to_rfc3339()), ("until", until.to_rfc3339())]);
+
+        let resp = self.execute(req, None).await?;
+        let overrides = resp.json::<OverridesResponse>().await?;
+
+        Ok(overrides.overrides)
+    }
+
     pub async fn create_schedule_override<Tz, O>(
         &self,
         u: &User,
         s: &Schedule,
         from: DateTime<Tz>,
         to: DateTime<Tz>,
-    ) -> reqwest::Result<()>
+    ) -> ClientResult<Sent<Override>>
     where
         Tz: TimeZone<Offset = O>,
         O: Display,
     {
-        let override_request = ScheduleOverrideRequest {
-            overrides: vec![ScheduleOverride {
-                start: from.to_rfc3339(),
-                end: to.to_rfc3339(),
-                user: UserRef {
-                    id: u.id.clone(),
-                    r#type: "user_reference".to_string(),
-                },
-            }],
+        let results = match self
+            .create_schedule_overrides(s, vec![ScheduleOverride::new(u, from, to)])
+            .await?
+        {
+            Sent::Done(results) => results,
+            Sent::DryRun => return Ok(Sent::DryRun),
         };
 
-        let client = reqwest::Client::new();
-        let req = client.post(format!(
-            "https://api.pagerduty.com/schedules/{}/overrides",
-            s.id
-        ));
-        let r2 = self.add_common_headers(req).json(&override_request);
+        match results.into_iter().next() {
+            Some(OverrideResult {
+                created: Some(created),
+                ..
+            }) => Ok(Sent::Done(created)),
+            Some(result) => Err(ClientError::Rejected(result.errors.join(", "))),
+            None => Err(ClientError::Rejected(String::from(
+                "no result was returned",
+            ))),
+        }
+    }
 
-        r2.send().await?;
+    /// Create several overrides on one schedule in a single request. The returned results are in
+    /// the same order as `overrides`.
+    pub async fn create_schedule_overrides(
+        &self,
+        s: &Schedule,
+        overrides: Vec<ScheduleOverride>,
+    ) -> ClientResult<Sent<Vec<OverrideResult>>> {
+        let override_request = ScheduleOverrideRequest { overrides };
+
+        let req = self
+            .http
+            .post(self.url(&format!("/schedules/{}/overrides", s.id)))
+            .json(&override_request);
+
+        if self.dry_run {
+            Self::print_request(req)?;
+            return Ok(Sent::DryRun);
+        }
+
+        // creating overrides isn't idempotent, so execute never retries this
+        let resp = self.execute(req, None).await?;
+        Ok(Sent::Done(resp.json::<Vec<OverrideResult>>().await?))
+    }
+
+    pub async fn delete_schedule_override(
+        &self,
+        s: &Schedule,
+        override_id: &str,
+    ) -> ClientResult<()> {
+        let req = self
+            .http
+            .delete(self.url(&format!("/schedules/{}/overrides/{}", s.id, override_id)));
+
+        if self.dry_run {
+            return Self::print_request(req);
+        }
+        self.execute(req, None).await?;
 
         Ok(())
     }
 
+    /// Print the method, URL and body a change would be sent with. Headers are left out, they hold
+    /// the API key.
+    fn print_request(req: RequestBuilder) -> ClientResult<()> {
+        let request = req.build()?;
+        println!(
+            "dry run, not sending {} {}",
+            request.method(),
+            request.url()
+        );
+
+        if let Some(body) = request.body().and_then(|b| b.as_bytes()) {
+            println!("{}", String::from_utf8_lossy(body));
+        }
+
+        Ok(())
+    }
+
+    /// Send a request with the common headers. Idempotent requests are retried with backoff when
+    /// pagerduty rate limits them or fails, progress is reported on `pb` while waiting. Anything
+    /// but a 2xx response ends up as an error.
+    async fn execute(
+        &self,
+        req: RequestBuilder,
+        pb: Option<&ProgressBar>,
+    ) -> ClientResult<Response> {
+        let mut request = self.add_common_headers(req).build()?;
+        if *request.method() != Method::GET {
+            let from = HeaderValue::from_str(self.sender_email().await?)
+                .map_err(|e| ClientError::MissingFrom(e.to_string()))?;
+            request.headers_mut().insert("From", from);
+        }
+        let idempotent = matches!(*request.method(), Method::GET | Method::DELETE);
+
+        let mut attempt = 0;
+        loop {
+            let attempted = request
+                .try_clone()
+                .expect("request bodies are never streamed");
+
+            let (failure, delay) = match self.http.execute(attempted).await {
+                Ok(resp) if resp.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    let delay = retry_delay(resp.headers(), attempt, rand::random());
+                    (ClientError::RateLimited(delay.as_secs()), delay)
+                }
+                Ok(resp) if resp.status().is_server_error() => {
+                    let delay = retry_delay(resp.headers(), attempt, rand::random());
+                    (Self::check_status(resp).await.unwrap_err(), delay)
+                }
+                Ok(resp) => return Self::check_status(resp).await,
+                Err(e) if e.is_connect() || e.is_timeout() => {
+                    let delay = retry_delay(&HeaderMap::new(), attempt, rand::random());
+                    (ClientError::from(e), delay)
+                }
+                Err(e) => return Err(ClientError::from(e)),
+            };
+
+            attempt += 1;
+            if !idempotent || attempt >= MAX_ATTEMPTS {
+                return Err(failure);
+            }
+
+            Self::wait(&failure, delay, pb).await;
+        }
+    }
+
+    /// Wait `delay` before retrying after `failure`, counting down on `pb` if there is one.
+    async fn wait(failure: &ClientError, delay: Duration, pb: Option<&ProgressBar>) {
+        let Some(pb) = pb else {
+            eprintln!("{failure}, retrying in {}s", delay.as_secs());
+            tokio::time::sleep(delay).await;
+            return;
+        };
+
+        let mut left = delay;
+        while !left.is_zero() {
+            pb.set_message(format!(
+                "{failure}, retrying in {}s",
+                left.as_secs_f64().ceil()
+            ));
+            let step = left.min(Duration::from_secs(1));
+            tokio::time::sleep(step).await;
+            left -= step;
+        }
+        pb.set_message("");
+    }
+
+    /// The email changes are made as. Pagerduty requires it in the `From` header of writes made
+    /// with account level API keys, and uses it for the audit trail of all others.
+    async fn sender_email(&self) -> ClientResult<&str> {
+        if let Some(email) = &self.profile.from_email {
+            return Ok(email);
+        }
+
+        // looked up without execute, which needs this for writes
+        let email = self
+            .from
+            .get_or_try_init(|| async {
+                let req = self.add_common_headers(self.http.get(self.url("/users/me")));
+                let resp = Self::check_status(self.http.execute(req.build()?).await?).await?;
+                Ok::<_, ClientError>(resp.json::<MeResponse>().await?.user.email)
+            })
+            .await
+            .map_err(|e| ClientError::MissingFrom(e.to_string()))?;
+
+        Ok(email)
+    }
+
+    async fn check_status(resp: Response) -> ClientResult<Response> {
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(resp);
+        }
+
+        let body = resp.text().await?;
+        let message = match serde_json::from_str::<ErrorResponse>(&body) {
+            Ok(e) if e.error.errors.is_empty() => e.error.message,
+            Ok(e) => format!("{} ({})", e.error.message, e.error.errors.join(", ")),
+            Err(_) => body,
+        };
+
+        Err(ClientError::Api { status, message })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
     fn add_common_headers(&self, req: RequestBuilder) -> RequestBuilder {
-        let mut api_key_value = String::from("Token token=");
-        api_key_value.push_str(&self.api_key);
+        let authorization = match self.profile.auth {
+            AuthKind::ApiKey => format!("Token token={}", self.api_key),
+            AuthKind::Oauth => format!("Bearer {}", self.api_key),
+        };
 
-        req.header("Authorization", api_key_value)
+        req.header("Authorization", authorization)
             .header("Accept", "application/vnd.pagerduty+json;version=2")
             .header("Content-Type", "application/json")
     }
 }
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::mockserver::{FakePagerduty, MockServer};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_get_users_paginates() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = Client::with_base_url(String::from("test-key"), server.url.clone());
+
+        let users = client.get_users(ProgressBar::hidden()).await.unwrap();
+        let emails: Vec<&str> = users.iter().map(|u| u.email.as_str()).collect();
+        assert_eq!(
+            emails,
+            vec![
+                "human.mcperson@microsoft.com",
+                "real.human@yahoo.com",
+                "jim.normallastname@gmail.com",
+                "coolguy.mcgee@gmail.com"
+            ]
+        );
+
+        let offsets: Vec<Option<String>> = server
+            .requests()
+            .iter()
+            .map(|r| {
+                r.query
+                    .iter()
+                    .find(|(k, _)| k == "offset")
+                    .map(|(_, v)| v.clone())
+            })
+            .collect();
+        assert_eq!(offsets, vec![Some("0".into()), Some("2".into())]);
+    }
+
+    #[tokio::test]
+    async fn test_get_schedules_and_me() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = Client::with_base_url(String::from("test-key"), format!("{}/", server.url));
+
+        let schedules = client.get_schedules(ProgressBar::hidden()).await.unwrap();
+        assert_eq!(schedules.len(), 3);
+        assert_eq!(schedules[2].name, "[primary] unimportant schedule");
+
+        let me = client.get_me().await.unwrap();
+        assert_eq!(me.email, "human.mcperson@microsoft.com");
+
+        for req in server.requests() {
+            assert_eq!(req.header("authorization"), Some("Token token=test-key"));
+            assert_eq!(
+                req.header("accept"),
+                Some("application/vnd.pagerduty+json;version=2")
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_teams() {
+        let mut fake = FakePagerduty::from_fixture();
+        fake.teams = vec![json!({ "id": "PT1", "name": "Platform" })];
+        let server = MockServer::start(fake).await;
+        let client = Client::with_base_url(String::from("test-key"), server.url.clone());
+
+        let teams = client.get_teams().await.unwrap();
+        assert_eq!(teams.len(), 1);
+        assert_eq!(teams[0].name, "Platform");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client =
+            Client::with_base_url(String::from("test-key"), server.url.clone()).with_dry_run(true);
+        let schedule = Schedule {
+            id: String::from("P3456"),
+            ..Default::default()
+        };
+        let user = User {
+            id: String::from("P12345"),
+            ..Default::default()
+        };
+        let from = chrono::Utc::now();
+
+        let results = client
+            .create_schedule_overrides(
+                &schedule,
+                vec![
+                    ScheduleOverride::new(&user, from, from + chrono::Duration::hours(1)),
+                    ScheduleOverride::new(&user, from, from + chrono::Duration::hours(2)),
+                ],
+            )
+            .await
+            .unwrap();
+        assert!(matches!(results, Sent::DryRun));
+        client
+            .delete_schedule_override(&schedule, "PO1")
+            .await
+            .unwrap();
+
+        assert!(server.requests().is_empty());
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_delay(&headers, 0, 1.0), Duration::from_secs(1));
+        assert_eq!(retry_delay(&headers, 2, 1.0), Duration::from_secs(4));
+        assert_eq!(retry_delay(&headers, 2, 0.0), Duration::from_secs(2));
+        assert_eq!(retry_delay(&headers, 10, 1.0), MAX_BACKOFF);
+
+        headers.insert("ratelimit-reset", "7".parse().unwrap());
+        assert_eq!(retry_delay(&headers, 0, 0.5), Duration::from_secs(7));
+
+        headers.insert("retry-after", "3".parse().unwrap());
+        assert_eq!(retry_delay(&headers, 0, 0.5), Duration::from_secs(3));
+
+        // waited for no longer than it takes the rate limit to reset
+        headers.insert("retry-after", "45".parse().unwrap());
+        assert_eq!(retry_delay(&headers, 0, 0.5), Duration::from_secs(45));
+        headers.insert("retry-after", "3600".parse().unwrap());
+        assert_eq!(retry_delay(&headers, 0, 0.5), MAX_RATE_LIMIT_WAIT);
+    }
+
+    #[tokio::test]
+    async fn test_retries_rate_limited_gets() {
+        let mut fake = FakePagerduty::from_fixture();
+        fake.rate_limited = 2;
+        let server = MockServer::start(fake).await;
+        let client = Client::with_base_url(String::from("test-key"), server.url.clone());
+
+        let me = client.get_me().await.unwrap();
+        assert_eq!(me.email, "human.mcperson@microsoft.com");
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_never_retries_override_post() {
+        let mut fake = FakePagerduty::from_fixture();
+        fake.rate_limited = 1;
+        let server = MockServer::start(fake).await;
+        let client = Client::with_base_url(String::from("test-key"), server.url.clone())
+            .with_profile(Profile {
+                from_email: Some(String::from("human.mcperson@microsoft.com")),
+                ..Profile::default()
+            });
+
+        let schedule = Schedule {
+            id: String::from("P3456"),
+            name: String::from("[primary] important schedule"),
+            ..Schedule::default()
+        };
+        let user = User {
+            id: String::from("P12345"),
+            email: String::from("real.human@yahoo.com"),
+            ..User::default()
+        };
+        let now = chrono::Utc::now();
+        let result = client
+            .create_schedule_override(&user, &schedule, now, now + chrono::Duration::hours(1))
+            .await;
+
+        assert!(matches!(result, Err(ClientError::RateLimited(0))));
+        let posts = server
+            .requests()
+            .into_iter()
+            .filter(|r| r.method == "POST")
+            .count();
+        assert_eq!(posts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_api_errors_are_readable() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = Client::with_base_url(String::from("test-key"), server.url.clone());
+
+        let err = client
+            .delete_schedule_override(
+                &Schedule {
+                    id: String::from("P3456"),
+                    name: String::new(),
+                    ..Schedule::default()
+                },
+                "PO1/nested",
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "pagerduty returned 404 Not Found: Not Found"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejected_override_is_an_error() {
+        let mut fake = FakePagerduty::from_fixture();
+        fake.rejecting_overrides = true;
+        let server = MockServer::start(fake).await;
+        let client = Client::with_base_url(String::from("test-key"), server.url.clone());
+
+        let schedule = Schedule {
+            id: String::from("P3456"),
+            ..Schedule::default()
+        };
+        let user = User {
+            id: String::from("P12345"),
+            ..User::default()
+        };
+        let now = chrono::Utc::now();
+        let err = client
+            .create_schedule_override(&user, &schedule, now, now + chrono::Duration::hours(1))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "pagerduty did not create the override: Invalid override"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_oauth_tokens_and_from_header() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = Client::with_base_url(String::from("test-token"), server.url.clone())
+            .with_profile(Profile {
+                auth: AuthKind::Oauth,
+                ..Profile::default()
+            });
+
+        let schedule = Schedule {
+            id: String::from("P3456"),
+            name: String::new(),
+            ..Schedule::default()
+        };
+        client
+            .delete_schedule_override(&schedule, "PO1")
+            .await
+            .unwrap();
+        client
+            .delete_schedule_override(&schedule, "PO2")
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        for req in &requests {
+            assert_eq!(req.header("authorization"), Some("Bearer test-token"));
+        }
+        // the current user is looked up once, and only sent along with changes
+        let paths: Vec<(&str, &str, Option<&str>)> = requests
+            .iter()
+            .map(|r| (r.method.as_str(), r.path.as_str(), r.header("from")))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("GET", "/users/me", None),
+                (
+                    "DELETE",
+                    "/schedules/P3456/overrides/PO1",
+                    Some("human.mcperson@microsoft.com")
+                ),
+                (
+                    "DELETE",
+                    "/schedules/P3456/overrides/PO2",
+                    Some("human.mcperson@microsoft.com")
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_scopes_explains_missing_scopes() {
+        let mut fake = FakePagerduty::from_fixture();
+        fake.forbidden = vec!["/oncalls"];
+        let server = MockServer::start(fake).await;
+        let client = Client::with_base_url(String::from("test-token"), server.url.clone());
+
+        let err = client.check_scopes().await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "this token is missing the oncalls.read scope(s). Grant the app users.read, \
+             schedules.read, oncalls.read and schedules.write in pagerduty, then enter the new token"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_key_owner() {
+        let mut fake = FakePagerduty::from_fixture();
+        fake.me["html_url"] = serde_json::json!("https://acme.pagerduty.com/users/P12345");
+        let server = MockServer::start(fake).await;
+        let client = Client::with_base_url(String::from("test-key"), server.url.clone());
+
+        let owner = client.key_owner().await.unwrap();
+        assert_eq!(owner.account, "acme");
+        assert_eq!(
+            owner.to_string(),
+            "human.mcperson@microsoft.com (P12345) in the acme account"
+        );
+    }
+
+    #[test]
+    fn test_labels_of_full_and_cached_models() {
+        let user: User = serde_json::from_value(serde_json::json!({
+            "id": "P12345",
+            "email": "jane@example.com",
+            "name": "Jane Doe",
+            "time_zone": "Europe/Paris",
+            "role": "user",
+            "job_title": null,
+            "teams": [{ "id": "PT1", "type": "team_reference", "summary": "Platform" }],
+        }))
+        .unwrap();
+        assert_eq!(user.label(), "jane@example.com - Jane Doe [Platform]");
+        assert_eq!(user.time_zone.as_deref(), Some("Europe/Paris"));
+
+        let schedule: Schedule = serde_json::from_value(serde_json::json!({
+            "id": "P3456",
+            "name": "primary",
+            "time_zone": "America/New_York",
+            "description": null,
+            "escalation_policies": [{ "id": "PE1", "summary": "Platform EP" }],
+        }))
+        .unwrap();
+        assert_eq!(schedule.label(), "primary (America/New_York)");
+        assert_eq!(schedule.escalation_policies[0].summary, "Platform EP");
+
+        // caches from before these fields existed still load
+        let cached: User =
+            serde_json::from_str(r#"{"id": "P12345", "email": "jane@example.com"}"#).unwrap();
+        assert_eq!(cached.label(), "jane@example.com");
+    }
+
+    #[test]
+    fn test_split_pem_bundle() {
+        let bundle = "# corporate root\n-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n\n-----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----\n";
+
+        assert_eq!(
+            split_pem_bundle(bundle),
+            vec![
+                "# corporate root\n-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----",
+                "-----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----",
+            ]
+        );
+    }
+}
@@ -0,0 +1,329 @@
+//! Defaults read from `config.toml` in the config directory. Command line flags win over the
+//! config, and a profile's own settings win over the config for that profile.
+
+use std::{error::Error, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use toml::Value;
+
+use crate::{client::HttpOptions, fuzzyselect::Selector, paths, persistence, timeparse::Workday};
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Used when neither --time-zone nor the profile give one.
+    pub time_zone: Option<String>,
+    /// Schedules `who` shows and `create` offers when no --schedule is given.
+    pub schedules: Vec<String>,
+    /// More time zones to show a new override in, besides yours and the schedule's.
+    pub zones: Vec<String>,
+    /// Overrides longer than this many hours are warned about before they are created.
+    pub long_override_hours: Option<u64>,
+    /// Always create overrides for yourself, like --me.
+    pub me: bool,
+    /// Don't ask before making changes.
+    pub skip_confirm: bool,
+    pub selector: Selector,
+    /// Offer users and schedules of your own teams before others.
+    pub prefer_my_teams: bool,
+    /// How many hours cached users and schedules are used before they are fetched again.
+    pub cache_ttl_hours: Option<u64>,
+    pub workday: Workday,
+    /// Send every request through this proxy, unless `PD_QUICK_OVERRIDE_PROXY` is set.
+    pub proxy: Option<String>,
+    /// PEM files with extra root certificates to trust, unless `PD_QUICK_OVERRIDE_CA_CERTS` is set.
+    pub ca_certs: Vec<PathBuf>,
+}
+
+impl Config {
+    /// Load the config file. Unknown and invalid settings are skipped with a warning, only a file
+    /// that isn't TOML at all fails to load.
+    pub fn load() -> Result<Config, Box<dyn Error>> {
+        let config_file = Self::get_config_file()?;
+        if !config_file.exists() {
+            return Ok(Config::default());
+        }
+
+        let contents = std::fs::read_to_string(&config_file)?;
+        let table = toml::from_str(&contents)
+            .map_err(|e| format!("could not read {}: {e}", config_file.display()))?;
+        let (config, warnings) = Self::from_table(table);
+        for warning in warnings {
+            eprintln!("{}: {warning}", config_file.display());
+        }
+
+        Ok(config)
+    }
+
+    /// The settings in `table`, leaving out those that can't be used along with why.
+    fn from_table(mut table: toml::Table) -> (Config, Vec<String>) {
+        let mut warnings = Vec::new();
+        table.retain(|key, value| {
+            // sections like workday are known by the settings in them
+            let section = format!("{key}.");
+            if !KEYS.iter().any(|k| *k == key || k.starts_with(&section)) {
+                warnings.push(format!("ignoring unknown setting {key}"));
+                return false;
+            }
+
+            let alone = toml::Table::from_iter([(key.to_string(), value.clone())]);
+            match Value::Table(alone).try_into::<Config>() {
+                Ok(_) => true,
+                Err(e) => {
+                    warnings.push(format!("ignoring {key}: {e}"));
+                    false
+                }
+            }
+        });
+
+        let mut config: Config = Value::Table(table).try_into().unwrap_or_default();
+        if let Some(Err(e)) = config
+            .time_zone
+            .as_ref()
+            .map(|tz| tz.parse::<chrono_tz::Tz>())
+        {
+            warnings.push(format!("ignoring time_zone: {e}"));
+            config.time_zone = None;
+        }
+        config.zones.retain(|tz| match tz.parse::<chrono_tz::Tz>() {
+            Ok(_) => true,
+            Err(e) => {
+                warnings.push(format!("ignoring a zone: {e}"));
+                false
+            }
+        });
+        if let Err(e) = config.workday.validate() {
+            warnings.push(format!("ignoring workday: {e}"));
+            config.workday = Workday::default();
+        }
+
+        (config, warnings)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        std::fs::create_dir_all(paths::config_dir()?)?;
+        persistence::write_atomically(
+            &Self::get_config_file()?,
+            toml::to_string_pretty(self)?.as_bytes(),
+        )?;
+
+        Ok(())
+    }
+
+    /// The value of a setting like `time_zone` or `workday.start`, `None` if it isn't set.
+    pub fn get(&self, key: &str) -> Result<Option<String>, Box<dyn Error>> {
+        let table = Value::try_from(self)?;
+        check_key(key)?;
+
+        let mut value = &table;
+        for part in key.split('.') {
+            match value.get(part) {
+                Some(v) => value = v,
+                None => return Ok(None),
+            }
+        }
+
+        Ok(Some(match value {
+            Value::String(s) => s.clone(),
+            v => v.to_string(),
+        }))
+    }
+
+    /// Change a setting. `value` is read as TOML, like `true` or `["a", "b"]`, and anything that
+    /// isn't valid TOML as a plain string.
+    pub fn set(&self, key: &str, value: &str) -> Result<Config, Box<dyn Error>> {
+        check_key(key)?;
+        let parsed = toml::from_str::<toml::Table>(&format!("value = {value}"))
+            .ok()
+            .and_then(|mut t| t.remove("value"))
+            .unwrap_or_else(|| Value::String(value.to_string()));
+
+        let mut table = Value::try_from(self)?;
+        let (parents, last) = match key.rsplit_once('.') {
+            Some((parents, last)) => (Some(parents), last),
+            None => (None, key),
+        };
+        let mut parent = &mut table;
+        for part in parents.into_iter().flat_map(|p| p.split('.')) {
+            parent = parent
+                .as_table_mut()
+                .expect("config sections are tables")
+                .entry(part)
+                .or_insert_with(|| Value::Table(toml::Table::new()));
+        }
+        parent
+            .as_table_mut()
+            .expect("config sections are tables")
+            .insert(last.to_string(), parsed);
+
+        let config: Config = table
+            .try_into()
+            .map_err(|e| Box::<dyn Error>::from(format!("invalid value for {key}: {e}")))?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        for tz in self.time_zone.iter().chain(&self.zones) {
+            tz.parse::<chrono_tz::Tz>()?;
+        }
+        self.workday
+            .validate()
+            .map_err(|e| format!("invalid workday hours: {e}"))?;
+
+        Ok(())
+    }
+
+    pub fn cache_ttl(&self) -> Option<chrono::Duration> {
+        self.cache_ttl_hours
+            .map(|hours| chrono::Duration::hours(hours as i64))
+    }
+
+    /// How long an override can be before it is warned about, a day unless set.
+    pub fn long_override(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.long_override_hours.unwrap_or(24) as i64)
+    }
+
+    /// Network settings, the `PD_QUICK_OVERRIDE_*` environment variables win over the config.
+    pub fn http(&self) -> HttpOptions {
+        HttpOptions {
+            proxy: self.proxy.clone(),
+            ca_certs: self.ca_certs.clone(),
+            ..HttpOptions::default()
+        }
+        .with_env()
+    }
+
+    /// The extra time zones, which were checked when they were set or loaded.
+    pub fn zones(&self) -> Vec<chrono_tz::Tz> {
+        self.zones.iter().filter_map(|tz| tz.parse().ok()).collect()
+    }
+
+    pub fn to_toml(&self) -> Result<String, Box<dyn Error>> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    fn get_config_file() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(paths::config_dir()?.join("config.toml"))
+    }
+}
+
+/// Every setting, for telling apart a setting that isn't set from a typo.
+const KEYS: [&str; 13] = [
+    "time_zone",
+    "schedules",
+    "zones",
+    "long_override_hours",
+    "me",
+    "skip_confirm",
+    "selector",
+    "prefer_my_teams",
+    "cache_ttl_hours",
+    "workday.start",
+    "workday.end",
+    "proxy",
+    "ca_certs",
+];
+
+fn check_key(key: &str) -> Result<(), Box<dyn Error>> {
+    if KEYS.contains(&key) {
+        return Ok(());
+    }
+
+    Err(Box::from(format!(
+        "there is no setting {key}, the settings are {}",
+        KEYS.join(", ")
+    )))
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn test_get_and_set() {
+        let config = Config::default()
+            .set("time_zone", "Europe/Paris")
+            .unwrap()
+            .set("schedules", r#"["primary", "P3456"]"#)
+            .unwrap()
+            .set("me", "true")
+            .unwrap()
+            .set("selector", "builtin")
+            .unwrap()
+            .set("workday.start", "8:30am")
+            .unwrap();
+
+        assert_eq!(config.time_zone.as_deref(), Some("Europe/Paris"));
+        assert_eq!(config.schedules, vec!["primary", "P3456"]);
+        assert!(config.me);
+        assert_eq!(config.selector, Selector::Builtin);
+        assert_eq!(config.workday.start, "8:30am");
+        assert_eq!(config.workday.end, "5pm");
+
+        assert_eq!(
+            config.get("time_zone").unwrap().as_deref(),
+            Some("Europe/Paris")
+        );
+        assert_eq!(config.get("me").unwrap().as_deref(), Some("true"));
+        assert_eq!(config.get("cache_ttl_hours").unwrap(), None);
+
+        let reparsed: Config = toml::from_str(&config.to_toml().unwrap()).unwrap();
+        assert_eq!(reparsed, config);
+    }
+
+    #[test]
+    fn test_rejects_bad_settings() {
+        let config = Config::default();
+
+        assert!(config.set("timezone", "UTC").is_err());
+        assert!(config.get("workday.lunch").is_err());
+        assert!(config.set("time_zone", "Mars/Olympus_Mons").is_err());
+        assert!(config
+            .set("zones", r#"["UTC", "Mars/Olympus_Mons"]"#)
+            .is_err());
+        assert!(config.set("me", "yes please").is_err());
+        assert!(config.set("cache_ttl_hours", "-1").is_err());
+        assert!(config.set("workday.end", "whenever").is_err());
+    }
+
+    #[test]
+    fn test_skips_bad_settings_when_loading() {
+        let table = toml::from_str(
+            r#"
+            time_zone = "Europe/Paris"
+            timezone = "UTC"
+            me = "yes please"
+            zones = ["UTC", "America/NewYork"]
+            proxy = "http://proxy.corp:3128"
+
+            [workday]
+            start = "8am"
+            end = "whenever"
+            "#,
+        )
+        .unwrap();
+
+        let (config, warnings) = Config::from_table(table);
+
+        assert_eq!(config.time_zone.as_deref(), Some("Europe/Paris"));
+        assert_eq!(config.proxy.as_deref(), Some("http://proxy.corp:3128"));
+        assert_eq!(config.workday, Workday::default());
+        assert_eq!(config.zones, vec!["UTC"]);
+        assert!(!config.me);
+        assert_eq!(warnings.len(), 4, "{warnings:?}");
+        assert_eq!(warnings[1], "ignoring unknown setting timezone");
+    }
+
+    #[test]
+    fn test_skips_bad_time_zone_when_loading() {
+        let table = toml::from_str(r#"time_zone = "America/NewYork""#).unwrap();
+
+        let (config, warnings) = Config::from_table(table);
+
+        assert_eq!(config.time_zone, None);
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(warnings[0].starts_with("ignoring time_zone"));
+    }
+}
@@ -0,0 +1,291 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use chrono_tz::Tz;
+
+use crate::{
+    client::{
+        Client, ClientError, ClientResult, Oncall, Override, Schedule, ScheduleOverride, Sent, User,
+    },
+    table::format_time,
+};
+
+/// Everything that a new override for `user` on a schedule would collide with: overrides already
+/// layered on that schedule, and shifts the user already holds elsewhere.
+pub struct Conflicts {
+    pub overrides: Vec<Override>,
+    pub oncalls: Vec<Oncall>,
+}
+
+impl Conflicts {
+    pub async fn find(
+        client: &Client,
+        user: &User,
+        schedule: &Schedule,
+        from: DateTime<Tz>,
+        to: DateTime<Tz>,
+    ) -> ClientResult<Conflicts> {
+        let overrides = client.get_schedule_overrides(schedule, from, to).await?;
+        let oncalls = client
+            .get_oncalls(from, to, &[user.id.as_str()], &[])
+            .await?;
+
+        let from = from.with_timezone(&Utc);
+        let to = to.with_timezone(&Utc);
+        Ok(Conflicts {
+            overrides: overlapping_overrides(overrides, from, to),
+            oncalls: other_oncalls(oncalls, schedule, from, to),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty() && self.oncalls.is_empty()
+    }
+
+    pub fn print(&self, tz: Tz) {
+        println!("found conflicts:");
+        for o in &self.overrides {
+            println!(
+                "\tthere is already an override for {} from {} to {}",
+                o.user.summary,
+                format_time(&o.start.with_timezone(&tz)),
+                format_time(&o.end.with_timezone(&tz))
+            );
+        }
+
+        for o in &self.oncalls {
+            let on = o
+                .schedule
+                .as_ref()
+                .map_or(&o.escalation_policy.summary, |s| &s.summary);
+            let until = o.end.map_or(String::from("permanently"), |e| {
+                format!("until {}", format_time(&e.with_timezone(&tz)))
+            });
+            println!(
+                "\t{} is already on call on {} (level {}) {}",
+                o.user.summary, on, o.escalation_level, until
+            );
+        }
+    }
+
+    /// Trim the conflicting overrides on `schedule` to their parts outside `from` to `to`, so a
+    /// new override there replaces them. Overrides can't be changed, so the parts to keep are
+    /// created before the old override is deleted. Whatever fails, nothing ends up uncovered.
+    pub async fn trim_overrides(
+        &self,
+        client: &Client,
+        schedule: &Schedule,
+        from: DateTime<Tz>,
+        to: DateTime<Tz>,
+    ) -> ClientResult<()> {
+        for o in &self.overrides {
+            let kept: Vec<ScheduleOverride> = outside(o, from, to)
+                .into_iter()
+                .map(|(start, end)| ScheduleOverride::for_user_id(&o.user.id, start, end))
+                .collect();
+            if !kept.is_empty() {
+                if let Sent::Done(results) =
+                    client.create_schedule_overrides(schedule, kept).await?
+                {
+                    if let Some(failed) = results.iter().find(|r| r.created.is_none()) {
+                        return Err(ClientError::Rejected(failed.errors.join(", ")));
+                    }
+                }
+            }
+
+            client.delete_schedule_override(schedule, &o.id).await?;
+            if !client.dry_run() {
+                println!("trimmed override for {} ({})", o.user.summary, o.id);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The parts of override `o` before `from` and after `to`.
+fn outside(
+    o: &Override,
+    from: DateTime<Tz>,
+    to: DateTime<Tz>,
+) -> Vec<(DateTime<FixedOffset>, DateTime<FixedOffset>)> {
+    let from = from.with_timezone(&o.start.timezone());
+    let to = to.with_timezone(&o.end.timezone());
+
+    let mut parts = Vec::new();
+    if o.start < from {
+        parts.push((o.start, from));
+    }
+    if to < o.end {
+        parts.push((to, o.end));
+    }
+
+    parts
+}
+
+fn overlaps(
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> bool {
+    start.is_none_or(|s| s < to) && end.is_none_or(|e| from < e)
+}
+
+fn overlapping_overrides(
+    overrides: Vec<Override>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<Override> {
+    overrides
+        .into_iter()
+        .filter(|o| {
+            overlaps(
+                Some(o.start.with_timezone(&Utc)),
+                Some(o.end.with_timezone(&Utc)),
+                from,
+                to,
+            )
+        })
+        .collect()
+}
+
+/// Shifts on any schedule other than `schedule`, being on call there already is the point of an
+/// override.
+fn other_oncalls(
+    oncalls: Vec<Oncall>,
+    schedule: &Schedule,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<Oncall> {
+    let mut conflicting: Vec<Oncall> = Vec::new();
+    for o in oncalls {
+        if o.schedule.as_ref().is_some_and(|s| s.id == schedule.id) {
+            continue;
+        }
+
+        if !overlaps(
+            o.start.map(|s| s.with_timezone(&Utc)),
+            o.end.map(|e| e.with_timezone(&Utc)),
+            from,
+            to,
+        ) {
+            continue;
+        }
+
+        // the same shift is listed for every escalation policy using its schedule
+        let schedule_id = o.schedule.as_ref().map(|s| &s.id);
+        if conflicting
+            .iter()
+            .any(|c| c.schedule.as_ref().map(|s| &s.id) == schedule_id && c.start == o.start)
+        {
+            continue;
+        }
+
+        conflicting.push(o);
+    }
+
+    conflicting
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::client::Reference;
+    use chrono::TimeZone;
+
+    fn reference(id: &str) -> Reference {
+        Reference {
+            id: id.to_string(),
+            summary: id.to_string(),
+        }
+    }
+
+    fn oncall(schedule: Option<&str>, start: u32, end: u32) -> Oncall {
+        Oncall {
+            user: reference("PUSER"),
+            schedule: schedule.map(reference),
+            escalation_policy: reference("PPOLICY"),
+            escalation_level: 1,
+            start: Some(
+                Utc.with_ymd_and_hms(2023, 2, 11, start, 0, 0)
+                    .unwrap()
+                    .into(),
+            ),
+            end: Some(Utc.with_ymd_and_hms(2023, 2, 11, end, 0, 0).unwrap().into()),
+        }
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let at = |h| Utc.with_ymd_and_hms(2023, 2, 11, h, 0, 0).unwrap();
+
+        assert!(overlaps(Some(at(9)), Some(at(11)), at(10), at(12)));
+        assert!(overlaps(Some(at(10)), Some(at(12)), at(9), at(13)));
+        assert!(overlaps(None, None, at(10), at(12)));
+        assert!(overlaps(None, Some(at(11)), at(10), at(12)));
+
+        // touching ranges are handoffs, not conflicts
+        assert!(!overlaps(Some(at(8)), Some(at(10)), at(10), at(12)));
+        assert!(!overlaps(Some(at(12)), Some(at(14)), at(10), at(12)));
+        assert!(!overlaps(Some(at(12)), None, at(10), at(12)));
+    }
+
+    #[test]
+    fn test_other_oncalls() {
+        let schedule = Schedule {
+            id: String::from("PSELECTED"),
+            name: String::from("selected"),
+            ..Schedule::default()
+        };
+        let from = Utc.with_ymd_and_hms(2023, 2, 11, 10, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2023, 2, 11, 12, 0, 0).unwrap();
+
+        let conflicting = other_oncalls(
+            vec![
+                oncall(Some("PSELECTED"), 9, 13),
+                oncall(Some("POTHER"), 9, 11),
+                oncall(Some("POTHER"), 9, 11),
+                oncall(Some("PLATER"), 12, 14),
+                oncall(None, 11, 12),
+            ],
+            &schedule,
+            from,
+            to,
+        );
+
+        let ids: Vec<Option<&str>> = conflicting
+            .iter()
+            .map(|o| o.schedule.as_ref().map(|s| s.id.as_str()))
+            .collect();
+        assert_eq!(ids, vec![Some("POTHER"), None]);
+    }
+
+    #[test]
+    fn test_outside() {
+        let at = |h| Utc.with_ymd_and_hms(2023, 2, 11, h, 0, 0).unwrap();
+        let o = |start, end| Override {
+            id: String::from("PO1"),
+            start: at(start).into(),
+            end: at(end).into(),
+            user: reference("PUSER"),
+        };
+        let from = at(10).with_timezone(&Tz::UTC);
+        let to = at(12).with_timezone(&Tz::UTC);
+
+        assert_eq!(
+            outside(&o(8, 14), from, to),
+            vec![
+                (at(8).into(), at(10).into()),
+                (at(12).into(), at(14).into())
+            ]
+        );
+        assert_eq!(
+            outside(&o(8, 11), from, to),
+            vec![(at(8).into(), at(10).into())]
+        );
+        assert_eq!(
+            outside(&o(11, 14), from, to),
+            vec![(at(12).into(), at(14).into())]
+        );
+        assert!(outside(&o(10, 12), from, to).is_empty());
+    }
+}
@@ -0,0 +1,392 @@
+use chrono::{DateTime, Duration};
+use chrono_tz::Tz;
+
+use crate::{
+    client::{Client, Override, Schedule, Sent, User},
+    conflicts::Conflicts,
+    persistence::Database,
+    prompt::Prompt,
+    table::{self, format_duration, format_time},
+    timeline::{self, Proposed},
+};
+
+pub struct Options {
+    pub tz: Tz,
+    pub from: DateTime<Tz>,
+    pub to: DateTime<Tz>,
+    /// Create the override for the current user instead of selecting one
+    pub me: bool,
+    /// Refuse to create an override that conflicts with anything
+    pub strict: bool,
+    /// The user (or alias) to create the override for, selected if not given
+    pub user: Option<String>,
+    /// The schedule (or alias) to create the override on, selected if not given
+    pub schedule: Option<String>,
+    /// Schedules to select from when `schedule` isn't given, any schedule when empty
+    pub schedules: Vec<String>,
+    /// Time zones to show the override in besides `tz` and the schedule's
+    pub zones: Vec<Tz>,
+    /// Overrides longer than this are warned about
+    pub long_override: Duration,
+}
+
+pub async fn run(client: &Client, db: &Database<'_>, prompt: &mut impl Prompt, opts: Options) {
+    let Options {
+        tz,
+        from,
+        to,
+        me,
+        strict,
+        user,
+        schedule,
+        schedules,
+        zones,
+        long_override,
+    } = opts;
+
+    let current_user;
+    let selected_user = if me {
+        current_user = client.get_me().await.unwrap();
+        &current_user
+    } else {
+        db.pick_user(prompt, user.as_deref())
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{e}");
+                std::process::exit(1);
+            })
+    };
+
+    let selected_schedule = if schedule.is_none() && !schedules.is_empty() {
+        db.pick_schedule_among(prompt, &schedules).await
+    } else {
+        db.pick_schedule(prompt, schedule.as_deref()).await
+    };
+    let selected_schedule = selected_schedule.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    });
+
+    let conflicts = Conflicts::find(client, selected_user, selected_schedule, from, to)
+        .await
+        .expect("could not check for conflicts");
+
+    print_timeline(client, selected_user, selected_schedule, from, to).await;
+    println!("will create an override for {selected_user} on {selected_schedule}:");
+    let schedule_tz = selected_schedule
+        .time_zone
+        .as_deref()
+        .and_then(|tz| tz.parse().ok());
+    let zones: Vec<Tz> = [tz].into_iter().chain(schedule_tz).chain(zones).collect();
+    table::print(&["zone", "from", "to"], &times_in(&zones, from, to));
+
+    let length = to - from;
+    println!("lasting {}", format_duration(length));
+    if length > long_override {
+        println!(
+            "warning: this override is longer than {}, check the dates",
+            format_duration(long_override)
+        );
+    }
+    println!("confirm to continue.");
+    let mut replace = false;
+    let proceed = if conflicts.is_empty() {
+        prompt.confirm()
+    } else {
+        conflicts.print(tz);
+        if strict {
+            eprintln!("refusing to create a conflicting override with --strict");
+            std::process::exit(1);
+        }
+
+        let answer = if conflicts.overrides.is_empty() {
+            prompt.choose(&["proceed", "abort"])
+        } else {
+            prompt.choose(&["proceed", "replace", "abort"])
+        };
+
+        replace = answer == "replace";
+        answer != "abort"
+    };
+
+    if proceed {
+        let replaced = if replace { Some(&conflicts) } else { None };
+        let created = create(client, selected_user, selected_schedule, from, to, replaced)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{e}");
+                std::process::exit(1);
+            });
+        match created {
+            Sent::Done(_) => println!("Override created! Good luck! "),
+            Sent::DryRun => println!("dry run, no override was created"),
+        }
+    }
+}
+
+/// Create the override, then trim the `replaced` overrides around it. They are only touched once
+/// the new override exists, so a failure never leaves the time uncovered.
+async fn create(
+    client: &Client,
+    user: &User,
+    schedule: &Schedule,
+    from: DateTime<Tz>,
+    to: DateTime<Tz>,
+    replaced: Option<&Conflicts>,
+) -> Result<Sent<Override>, String> {
+    let created = client
+        .create_schedule_override(user, schedule, from, to)
+        .await
+        .map_err(|e| format!("could not create override: {e}"))?;
+
+    if let Some(conflicts) = replaced {
+        conflicts
+            .trim_overrides(client, schedule, from, to)
+            .await
+            .map_err(|e| format!("created the override, but could not trim the others: {e}"))?;
+    }
+
+    Ok(created)
+}
+
+/// Draw the schedule around the new override. It only helps to check the override, so failing to
+/// fetch the schedule is a warning.
+async fn print_timeline(
+    client: &Client,
+    user: &User,
+    schedule: &Schedule,
+    from: DateTime<Tz>,
+    to: DateTime<Tz>,
+) {
+    let who = if user.name.is_empty() {
+        &user.email
+    } else {
+        &user.name
+    };
+    let proposed = Proposed { who, from, to };
+    let (since, until) = timeline::window(&proposed);
+
+    let entries = client.get_schedule_entries(schedule, since, until).await;
+    let overrides = client.get_schedule_overrides(schedule, since, until).await;
+    match (entries, overrides) {
+        (Ok(entries), Ok(overrides)) => {
+            let schedule_tz = schedule.time_zone.as_deref().and_then(|tz| tz.parse().ok());
+            print!(
+                "{}",
+                timeline::render(schedule_tz, &entries, &overrides, &proposed)
+            );
+        }
+        (Err(e), _) | (_, Err(e)) => eprintln!("could not draw the schedule: {e}"),
+    }
+}
+
+/// A row for each of `zones` with `from` and `to` in it, the same zone only once.
+fn times_in(zones: &[Tz], from: DateTime<Tz>, to: DateTime<Tz>) -> Vec<Vec<String>> {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for zone in zones {
+        if rows.iter().any(|r| r[0] == zone.name()) {
+            continue;
+        }
+
+        rows.push(vec![
+            zone.name().to_string(),
+            format_time(&from.with_timezone(zone)),
+            format_time(&to.with_timezone(zone)),
+        ]);
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::{
+        mockserver::{FakePagerduty, MockServer},
+        prompt::Scripted,
+    };
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn test_create_end_to_end() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = Client::with_base_url(String::from("test-key"), server.url.clone());
+        let storage_dir = tempfile::tempdir().unwrap();
+
+        let db = Database::load_from(&client, storage_dir.path().to_path_buf(), None, &[])
+            .await
+            .unwrap();
+        assert_eq!(db.storage.users.len(), 4);
+        assert!(storage_dir.path().join("storage.json").exists());
+
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let from = tz.with_ymd_and_hms(2023, 2, 11, 16, 0, 0).unwrap();
+        let to = tz.with_ymd_and_hms(2023, 2, 11, 17, 30, 0).unwrap();
+        let mut prompt = Scripted {
+            selections: vec!["real.human@yahoo.com", "[secondary] important schedule"],
+            answers: vec!["yes"],
+        };
+
+        run(
+            &client,
+            &db,
+            &mut prompt,
+            Options {
+                tz,
+                from,
+                to,
+                me: false,
+                strict: false,
+                user: None,
+                schedule: None,
+                schedules: vec![],
+                zones: vec![],
+                long_override: Duration::hours(24),
+            },
+        )
+        .await;
+
+        let requests = server.requests();
+        let created = requests
+            .iter()
+            .find(|r| r.method == "POST")
+            .expect("no override was created");
+        assert_eq!(created.path, "/schedules/P3456/overrides");
+        assert_eq!(created.header("from"), Some("human.mcperson@microsoft.com"));
+        assert_eq!(
+            created.json(),
+            serde_json::json!({
+                "overrides": [{
+                    "start": "2023-02-11T16:00:00-05:00",
+                    "end": "2023-02-11T17:30:00-05:00",
+                    "user": { "id": "P12345", "type": "user_reference" },
+                }]
+            })
+        );
+        assert!(prompt.answers.is_empty());
+    }
+
+    /// A server with an override for P12345 on P3456 from 14:00 to 17:00 New York time on
+    /// 2023-02-11, and the conflicts a new override from 16:00 to 17:30 has with it.
+    async fn replacing(rejecting: bool) -> (MockServer, Client, DateTime<Tz>, DateTime<Tz>) {
+        let mut fake = FakePagerduty::from_fixture();
+        fake.rejecting_overrides = rejecting;
+        fake.overrides.push((
+            String::from("P3456"),
+            serde_json::json!({
+                "id": "POLD",
+                "start": "2023-02-11T14:00:00-05:00",
+                "end": "2023-02-11T17:00:00-05:00",
+                "user": { "id": "P12345", "summary": "real human" },
+            }),
+        ));
+        let server = MockServer::start(fake).await;
+        let client = Client::with_base_url(String::from("test-key"), server.url.clone());
+
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let from = tz.with_ymd_and_hms(2023, 2, 11, 16, 0, 0).unwrap();
+        let to = tz.with_ymd_and_hms(2023, 2, 11, 17, 30, 0).unwrap();
+        (server, client, from, to)
+    }
+
+    fn schedule_and_user() -> (Schedule, User) {
+        let schedule = Schedule {
+            id: String::from("P3456"),
+            ..Schedule::default()
+        };
+        let user = User {
+            id: String::from("P99999"),
+            ..User::default()
+        };
+        (schedule, user)
+    }
+
+    #[tokio::test]
+    async fn test_replace_trims_overrides() {
+        let (_server, client, from, to) = replacing(false).await;
+        let (schedule, user) = schedule_and_user();
+        let conflicts = Conflicts::find(&client, &user, &schedule, from, to)
+            .await
+            .unwrap();
+        assert_eq!(conflicts.overrides.len(), 1);
+
+        create(&client, &user, &schedule, from, to, Some(&conflicts))
+            .await
+            .unwrap();
+
+        let mut left: Vec<(String, String, String)> = client
+            .get_schedule_overrides(&schedule, from, to)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|o| (o.user.id, o.start.to_rfc3339(), o.end.to_rfc3339()))
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            vec![
+                (
+                    String::from("P12345"),
+                    String::from("2023-02-11T14:00:00-05:00"),
+                    String::from("2023-02-11T16:00:00-05:00"),
+                ),
+                (
+                    String::from("P99999"),
+                    String::from("2023-02-11T16:00:00-05:00"),
+                    String::from("2023-02-11T17:30:00-05:00"),
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_replace_keeps_overrides() {
+        let (server, client, from, to) = replacing(true).await;
+        let (schedule, user) = schedule_and_user();
+        let conflicts = Conflicts::find(&client, &user, &schedule, from, to)
+            .await
+            .unwrap();
+
+        let err = create(&client, &user, &schedule, from, to, Some(&conflicts))
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("could not create override"));
+
+        let left = client
+            .get_schedule_overrides(&schedule, from, to)
+            .await
+            .unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].id, "POLD");
+        assert!(server.requests().iter().all(|r| r.method != "DELETE"));
+    }
+
+    #[test]
+    fn test_times_in() {
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let paris: Tz = "Europe/Paris".parse().unwrap();
+        let from = tz.with_ymd_and_hms(2023, 2, 11, 16, 0, 0).unwrap();
+        let to = tz.with_ymd_and_hms(2023, 2, 12, 1, 30, 0).unwrap();
+
+        assert_eq!(
+            times_in(&[tz, paris, tz, Tz::UTC], from, to),
+            vec![
+                vec![
+                    "America/New_York",
+                    "Sat 2023-02-11 16:00 EST",
+                    "Sun 2023-02-12 01:30 EST"
+                ],
+                vec![
+                    "Europe/Paris",
+                    "Sat 2023-02-11 22:00 CET",
+                    "Sun 2023-02-12 07:30 CET"
+                ],
+                vec![
+                    "UTC",
+                    "Sat 2023-02-11 21:00 UTC",
+                    "Sun 2023-02-12 06:30 UTC"
+                ],
+            ]
+        );
+    }
+}
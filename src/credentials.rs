@@ -0,0 +1,197 @@
+//! Where a profile's pagerduty token comes from. The OS keyring isn't available on headless
+//! servers and in containers, so the token can also be given in the environment, printed by a
+//! command or read from a file. Sources are tried in that order, the keyring comes last.
+
+use std::{
+    error::Error,
+    fmt::Display,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::profile::Profile;
+
+pub const TOKEN_VAR: &str = "PAGERDUTY_TOKEN";
+pub const TOKEN_COMMAND_VAR: &str = "PAGERDUTY_TOKEN_COMMAND";
+pub const TOKEN_FILE_VAR: &str = "PAGERDUTY_TOKEN_FILE";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Env,
+    Command(String),
+    File(PathBuf),
+    Keyring,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Env => write!(f, "the {TOKEN_VAR} environment variable"),
+            Source::Command(command) => write!(f, "the command `{command}`"),
+            Source::File(path) => write!(f, "the file {}", path.display()),
+            Source::Keyring => f.write_str("the OS keyring"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Credential {
+    pub secret: String,
+    pub source: Source,
+}
+
+/// Find the profile's token. `None` means no source is configured and the keyring has no entry,
+/// so the token has to be entered.
+pub fn find(profile: &Profile) -> Result<Option<Credential>, Box<dyn Error>> {
+    find_with(profile, |name| std::env::var(name).ok())
+}
+
+fn find_with(
+    profile: &Profile,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<Option<Credential>, Box<dyn Error>> {
+    if let Some(secret) = env(TOKEN_VAR).filter(|s| !s.trim().is_empty()) {
+        return Ok(Some(Credential {
+            secret: secret.trim().to_string(),
+            source: Source::Env,
+        }));
+    }
+
+    if let Some(command) = env(TOKEN_COMMAND_VAR).or_else(|| profile.token_command.clone()) {
+        return Ok(Some(Credential {
+            secret: from_command(&command)?,
+            source: Source::Command(command),
+        }));
+    }
+
+    if let Some(path) = env(TOKEN_FILE_VAR)
+        .map(PathBuf::from)
+        .or_else(|| profile.token_file.clone())
+    {
+        return Ok(Some(Credential {
+            secret: from_file(&path)?,
+            source: Source::File(path),
+        }));
+    }
+
+    match profile.keyring_entry().get_password() {
+        Ok(secret) => Ok(Some(Credential {
+            secret,
+            source: Source::Keyring,
+        })),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(Box::from(format!(
+            "could not read the OS keyring ({e}), give the token with {TOKEN_VAR}, \
+             {TOKEN_COMMAND_VAR} or {TOKEN_FILE_VAR} instead"
+        ))),
+    }
+}
+
+/// Run `command` with the shell and take the first line it prints as the token.
+fn from_command(command: &str) -> Result<String, Box<dyn Error>> {
+    let output = Command::new("sh").arg("-c").arg(command).output()?;
+    if !output.status.success() {
+        return Err(Box::from(format!(
+            "`{command}` failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    non_empty(stdout.lines().next().unwrap_or_default(), || {
+        format!("`{command}` printed no token")
+    })
+}
+
+/// Read the token from `path`, which nobody but its owner may be able to read.
+fn from_file(path: &Path) -> Result<String, Box<dyn Error>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(path)?.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            return Err(Box::from(format!(
+                "{} can be read by others (mode {mode:o}), restrict it with `chmod 600 {}`",
+                path.display(),
+                path.display()
+            )));
+        }
+    }
+
+    let contents = std::fs::read_to_string(path)?;
+    non_empty(&contents, || format!("{} is empty", path.display()))
+}
+
+fn non_empty(secret: &str, message: impl Fn() -> String) -> Result<String, Box<dyn Error>> {
+    match secret.trim() {
+        "" => Err(Box::from(message())),
+        secret => Ok(secret.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn test_sources_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let token_file = dir.path().join("token");
+        std::fs::write(&token_file, "file-token\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&token_file, std::fs::Permissions::from_mode(0o600)).unwrap();
+        }
+
+        let profile = Profile {
+            token_command: Some(String::from("echo command-token")),
+            token_file: Some(token_file.clone()),
+            ..Profile::default()
+        };
+
+        let found = find_with(&profile, |name| {
+            (name == TOKEN_VAR).then(|| String::from("env-token"))
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(found.secret, "env-token");
+        assert_eq!(found.source, Source::Env);
+
+        let found = find_with(&profile, |_| None).unwrap().unwrap();
+        assert_eq!(found.secret, "command-token");
+        assert_eq!(found.source.to_string(), "the command `echo command-token`");
+
+        let found = find_with(
+            &Profile {
+                token_command: None,
+                ..profile.clone()
+            },
+            |_| None,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(found.secret, "file-token");
+        assert_eq!(found.source, Source::File(token_file));
+    }
+
+    #[test]
+    fn test_rejects_failing_commands_and_open_files() {
+        assert!(from_command("exit 3").is_err());
+        assert!(from_command("true").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let token_file = dir.path().join("token");
+        std::fs::write(&token_file, "file-token").unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&token_file, std::fs::Permissions::from_mode(0o644)).unwrap();
+            let err = from_file(&token_file).unwrap_err();
+            assert!(err.to_string().contains("mode 644"), "{err}");
+        }
+    }
+}
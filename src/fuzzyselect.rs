@@ -1,39 +1,184 @@
 use std::{
-    collections::HashMap,
-    io::{self, Read, Write},
-    ops::Deref,
+    io::{self, Write},
     process::{Command, Stdio},
 };
 
-// TODO: can this work on &T to avoid the need to clone?
-pub fn select<'a, T>(ss: &'a HashMap<String, &T>) -> io::Result<&'a T>
-where
-    T: Clone,
-{
-    let mut subprocess = Command::new("fzf")
+use serde::{Deserialize, Serialize};
+
+/// The program used to pick one of many options.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Selector {
+    /// https://github.com/junegunn/fzf
+    #[default]
+    Fzf,
+    /// https://github.com/lotabout/skim, a drop-in for fzf
+    Skim,
+    /// Type part of an option and pick from a numbered list, for when neither is installed.
+    Builtin,
+}
+
+/// A command the finder runs to show more about the highlighted option next to the list.
+pub struct Preview<'c, T> {
+    /// A shell command line, run with the key of the option appended.
+    pub command: &'c str,
+    pub key: fn(&T) -> &str,
+}
+
+/// Select one of `options` by its label. The options are offered in the order given, which the
+/// finders keep as the tie breaker between equally good matches. The option selected is told
+/// apart by its position, so options with the same label can still be selected.
+/// The builtin selector has no room for a preview and ignores it.
+pub fn select<'a, T>(
+    selector: Selector,
+    options: &[(String, &'a T)],
+    preview: Option<&Preview<'_, T>>,
+) -> io::Result<&'a T> {
+    match selector {
+        Selector::Fzf | Selector::Skim => select_with(selector, options, preview),
+        Selector::Builtin => select_builtin(options),
+    }
+}
+
+fn select_with<'a, T>(
+    selector: Selector,
+    options: &[(String, &'a T)],
+    preview: Option<&Preview<'_, T>>,
+) -> io::Result<&'a T> {
+    let (program, homepage) = match selector {
+        Selector::Skim => ("sk", "https://github.com/lotabout/skim"),
+        _ => ("fzf", "https://github.com/junegunn/fzf"),
+    };
+
+    let mut subprocess = Command::new(program)
+        .args(finder_args(selector, preview.map(|p| p.command)))
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .spawn()
         .unwrap_or_else(|_| {
-            eprintln!("could not spawn fzf, is it installed? https://github.com/junegunn/fzf");
+            eprintln!("could not spawn {program}, is it installed? {homepage}");
             std::process::exit(1);
         });
 
-    let stdin = subprocess.stdin.as_mut().unwrap();
-    for k in ss.keys() {
-        stdin.write_all(k.as_bytes())?;
-        stdin.write_all("\n".as_bytes())?;
+    let mut stdin = subprocess.stdin.take().unwrap();
+    let written = options
+        .iter()
+        .enumerate()
+        .try_for_each(|(i, (label, option))| {
+            let key = preview.map_or("", |p| (p.key)(option));
+            writeln!(stdin, "{i}\t{label}\t{key}")
+        });
+    drop(stdin);
+
+    let output = subprocess.wait_with_output()?;
+    written?;
+
+    // nothing is printed when the user aborts with ctrl-C or escape
+    let selected = String::from_utf8_lossy(&output.stdout);
+    selected
+        .split_once('\t')
+        .and_then(|(i, _)| i.parse::<usize>().ok())
+        .and_then(|i| options.get(i))
+        .map(|(_, value)| *value)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Interrupted, "nothing was selected"))
+}
+
+/// The flags for fzf or skim. Every line is the option's index, its label and the key to preview
+/// it by, and only the label is shown. Both take the same flags, except that skim only knows the
+/// older, colon separated form of `--preview-window`.
+fn finder_args(selector: Selector, preview_command: Option<&str>) -> Vec<String> {
+    let mut args: Vec<String> = ["--tiebreak=index", "--delimiter=\t", "--with-nth=2"]
+        .map(String::from)
+        .to_vec();
+
+    if let Some(command) = preview_command {
+        let window = match selector {
+            Selector::Skim => "--preview-window=right:40%",
+            _ => "--preview-window=right,40%",
+        };
+        args.extend([
+            String::from("--preview"),
+            format!("{command} {{3}}"),
+            String::from(window),
+        ]);
     }
 
-    // TODO: check exit code in case the user did a ctrl-C
-    subprocess.wait()?;
+    args
+}
+
+/// The most options listed at once by the builtin selector.
+const MAX_LISTED: usize = 20;
+
+fn select_builtin<'a, T>(options: &[(String, &'a T)]) -> io::Result<&'a T> {
+    let sin = io::stdin();
+    let mut query = String::new();
+    loop {
+        let matching = matching_options(options, &query);
+        match matching.as_slice() {
+            [] => println!("nothing matches {query:?}"),
+            [(_, only)] => return Ok(only),
+            _ if matching.len() <= MAX_LISTED => {
+                for (i, (label, _)) in matching.iter().enumerate() {
+                    println!("{:>3}) {label}", i + 1);
+                }
+            }
+            _ => println!("{} options match, narrow them down", matching.len()),
+        }
+
+        print!("search or pick a number: ");
+        io::stdout().flush()?;
 
-    let mut stdout = subprocess.stdout.unwrap();
-    let mut selected_key = String::new();
-    stdout.read_to_string(&mut selected_key)?;
+        let mut answer = String::new();
+        sin.read_line(&mut answer)?;
+        let answer = answer.trim();
 
-    selected_key.truncate(selected_key.trim().len());
+        if let Some(picked) = answer
+            .parse::<usize>()
+            .ok()
+            .filter(|n| (1..=matching.len()).contains(n) && matching.len() <= MAX_LISTED)
+        {
+            return Ok(matching[picked - 1].1);
+        }
+        query = answer.to_string();
+    }
+}
+
+fn matching_options<'o, 'a, T>(
+    options: &'o [(String, &'a T)],
+    query: &str,
+) -> Vec<&'o (String, &'a T)> {
+    let query = query.to_lowercase();
+    options
+        .iter()
+        .filter(|(label, _)| label.to_lowercase().contains(&query))
+        .collect()
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
 
-    let value = ss.get(&selected_key).unwrap().deref();
-    Ok(value)
+    #[test]
+    fn test_finder_args() {
+        assert_eq!(
+            finder_args(Selector::Fzf, None),
+            vec!["--tiebreak=index", "--delimiter=\t", "--with-nth=2"]
+        );
+        assert_eq!(
+            finder_args(Selector::Fzf, Some("pdqo preview"))[3..],
+            [
+                "--preview",
+                "pdqo preview {3}",
+                "--preview-window=right,40%"
+            ]
+        );
+        assert_eq!(
+            finder_args(Selector::Skim, Some("pdqo preview"))[3..],
+            [
+                "--preview",
+                "pdqo preview {3}",
+                "--preview-window=right:40%"
+            ]
+        );
+    }
 }
@@ -1,19 +1,41 @@
-use std::{
-    collections::HashMap,
-    io::{self, Write},
-};
-
-use chrono::TimeZone;
+use chrono::{DateTime, TimeZone};
 use clap::{Parser, Subcommand};
-use client::Client;
+use client::{Client, User};
+use config::Config;
+use profile::{AuthKind, Profile, Region};
+use prompt::{Prompt, Terminal};
 
+mod aliases;
 mod client;
+mod config;
+mod conflicts;
+mod create;
+mod credentials;
 mod fuzzyselect;
+#[cfg(test)]
+mod mockserver;
+mod paths;
 mod persistence;
+mod preview;
+mod profile;
+mod prompt;
+mod swap;
+mod table;
+mod timeline;
 mod timeparse;
+mod vacation;
+mod who;
 
 #[derive(Debug, Parser)]
 struct Cli {
+    /// The pagerduty account profile to use
+    #[arg(long, global = true, env = "PD_QUICK_OVERRIDE_PROFILE", default_value = profile::DEFAULT_PROFILE)]
+    profile: String,
+
+    /// Keep the cache, settings and history in this directory instead of the platform's
+    #[arg(long, global = true, env = paths::DATA_DIR_VAR)]
+    data_dir: Option<std::path::PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -25,113 +47,710 @@ enum Commands {
         #[arg(short, long)]
         at: String,
 
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with = "user")]
         me: bool,
 
+        /// The user to create the override for, by email, ID or alias
+        #[arg(short, long)]
+        user: Option<String>,
+
+        /// The schedule to create the override on, by name, ID or alias
+        #[arg(short, long)]
+        schedule: Option<String>,
+
+        /// Only offer users and schedules of this team, by name or ID. Defaults to your own teams
+        #[arg(long)]
+        team: Vec<String>,
+
+        /// Offer users and schedules of every team
+        #[arg(long, conflicts_with = "team")]
+        all_teams: bool,
+
+        #[arg(short, long)]
+        time_zone: Option<String>,
+
+        /// Refuse to create an override that conflicts with existing overrides or shifts
+        #[arg(long)]
+        strict: bool,
+
+        /// Go through everything but print the changes instead of making them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Swap one upcoming shift between two people on the same schedule
+    #[command()]
+    Swap {
+        /// How many days ahead to look for shifts
+        #[arg(short, long, default_value_t = 14)]
+        days: i64,
+
+        /// The schedule to swap shifts on, by name, ID or alias
+        #[arg(short, long)]
+        schedule: Option<String>,
+
+        #[arg(short, long)]
+        time_zone: Option<String>,
+
+        /// Go through everything but print the changes instead of making them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Hand every shift you hold in a time range to someone else
+    #[command()]
+    Vacation {
+        #[arg(short, long)]
+        at: String,
+
+        /// Email, ID or alias of the person covering all of your shifts, instead of picking one
+        /// per shift. A group alias narrows down who can be picked
+        #[arg(short, long)]
+        cover: Option<String>,
+
+        #[arg(short, long)]
+        time_zone: Option<String>,
+
+        /// Go through everything but print the changes instead of making them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show who is on call now, or at a given time
+    #[command()]
+    Who {
+        #[arg(short, long)]
+        at: Option<String>,
+
+        /// Only show schedules whose name contains this (or with this ID or alias), can be
+        /// repeated
+        #[arg(short, long)]
+        schedule: Vec<String>,
+
         #[arg(short, long)]
         time_zone: Option<String>,
     },
     ResetApiKey {},
+    /// Show the account, user and API key source of the current profile
+    #[command()]
+    Whoami {},
+    /// Manage profiles for different pagerduty accounts
+    #[command()]
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+    /// Manage short names for users and schedules, and groups of them
+    #[command()]
+    Alias {
+        #[command(subcommand)]
+        command: AliasCommands,
+    },
+    /// Show and change defaults in the config file
+    #[command()]
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
     ResetStorage {},
+    /// Show who is on call on a schedule, for the preview next to schedules being selected
+    #[command(hide = true)]
+    Preview {
+        schedule_id: String,
+
+        #[arg(short, long)]
+        time_zone: Option<String>,
+    },
+}
+
+impl Commands {
+    /// The --time-zone given to a command that takes one.
+    fn time_zone(&self) -> Option<&str> {
+        match self {
+            Commands::Create { time_zone, .. }
+            | Commands::Swap { time_zone, .. }
+            | Commands::Vacation { time_zone, .. }
+            | Commands::Who { time_zone, .. }
+            | Commands::Preview { time_zone, .. } => time_zone.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum AliasCommands {
+    List {},
+    /// Add an alias for one or more users, or one or more schedules
+    Add {
+        name: String,
+
+        /// A user by email, ID or alias, can be repeated
+        #[arg(short, long)]
+        user: Vec<String>,
+
+        /// A schedule by name, ID or alias, can be repeated
+        #[arg(short, long)]
+        schedule: Vec<String>,
+    },
+    #[command(visible_alias = "remove")]
+    Rm {
+        name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigCommands {
+    /// Print every setting
+    Show {},
+    Get {
+        key: String,
+    },
+    /// Change a setting, e.g. `config set workday.start 8:30am`
+    Set {
+        key: String,
+        value: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ProfileCommands {
+    List {},
+    Add {
+        name: String,
+
+        /// The service region of the account, detected when the API key is entered if not given
+        #[arg(short, long, value_enum)]
+        region: Option<Region>,
+
+        /// The time zone used by commands of this profile when --time-zone isn't given
+        #[arg(short, long)]
+        time_zone: Option<String>,
+
+        /// Whether the key is a REST API key or an OAuth token, detected when it is entered if not
+        /// given
+        #[arg(long, value_enum)]
+        auth: Option<AuthKind>,
+
+        /// The email changes are made as, looked up from the key's user if not given
+        #[arg(long)]
+        from: Option<String>,
+
+        /// A command that prints the API key, instead of keeping it in the keyring
+        #[arg(long)]
+        token_command: Option<String>,
+
+        /// A file (readable only by you) holding the API key, instead of keeping it in the keyring
+        #[arg(long)]
+        token_file: Option<std::path::PathBuf>,
+    },
+    /// Change the settings of the current profile
+    Set {
+        /// The pagerduty service region the account lives in
+        #[arg(short, long, value_enum)]
+        region: Option<Region>,
+
+        #[arg(short, long)]
+        time_zone: Option<String>,
+
+        #[arg(long, value_enum)]
+        auth: Option<AuthKind>,
+
+        #[arg(long)]
+        from: Option<String>,
+
+        #[arg(long)]
+        token_command: Option<String>,
+
+        #[arg(long)]
+        token_file: Option<std::path::PathBuf>,
+    },
+    Remove {
+        name: String,
+    },
 }
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
+    paths::init(cli.data_dir.clone());
+    // a broken config shouldn't keep anything from running, least of all fixing it
+    let loaded = Config::load();
+    let config_unreadable = loaded.is_err();
+    let config = loaded.unwrap_or_else(|e| {
+        eprintln!("could not load config, using the defaults: {e}");
+        Config::default()
+    });
+    let mut terminal = Terminal {
+        selector: config.selector,
+        skip_confirm: config.skip_confirm,
+        preview_command: preview_command(&cli),
+    };
 
     match cli.command {
-        Commands::Create { at, time_zone, me } => {
-            let tz_string = time_zone.unwrap_or_else(|| {
-                iana_time_zone::get_timezone().expect("could not find timezone")
-            });
+        Commands::Create {
+            at,
+            time_zone,
+            me,
+            user,
+            schedule,
+            team,
+            all_teams,
+            strict,
+            dry_run,
+        } => {
+            let profile = open_profile(&cli.profile);
+            let tz = resolve_time_zone(time_zone, &profile, &config);
 
-            let tz: chrono_tz::Tz = tz_string.parse().unwrap();
+            let (from, to) = parse_range(&tz, &at, &config);
 
-            let now = chrono::Utc::now().timestamp();
-            tz.timestamp_opt(now, 0).unwrap();
-            let (from, to) = timeparse::parse(&tz.timestamp_opt(now, 0).unwrap(), at.as_str())
-                .unwrap_or_else(|e| {
-                    eprintln!("could not parse the time given time range: {:?}", e);
-                    eprintln!("here are some example time ranges: ");
+            let client = Client::new(profile, &config.http(), &mut terminal)
+                .await
+                .expect("could not open pagerduty client")
+                .with_dry_run(dry_run);
+            // looked up once for both the teams to offer and the teams to prefer
+            let current_user = if (team.is_empty() && !all_teams) || config.prefer_my_teams {
+                client
+                    .get_me()
+                    .await
+                    .map_err(|e| eprintln!("could not look up your teams: {e}"))
+                    .ok()
+            } else {
+                None
+            };
+            let teams = resolve_teams(&client, &team, all_teams, current_user.as_ref()).await;
+            let db = load_database(&client, &config, &teams, current_user.as_ref()).await;
 
-                    for example in timeparse::VALID_TIMES {
-                        eprintln!("\t{example}");
-                    }
+            create::run(
+                &client,
+                &db,
+                &mut terminal,
+                create::Options {
+                    tz,
+                    from,
+                    to,
+                    me: me || (config.me && user.is_none()),
+                    strict,
+                    user,
+                    schedule,
+                    schedules: config.schedules.clone(),
+                    zones: config.zones(),
+                    long_override: config.long_override(),
+                },
+            )
+            .await;
+        }
+        Commands::Swap {
+            days,
+            schedule,
+            time_zone,
+            dry_run,
+        } => {
+            let profile = open_profile(&cli.profile);
+            let tz = resolve_time_zone(time_zone, &profile, &config);
 
-                    std::process::exit(1);
-                });
+            let client = Client::new(profile, &config.http(), &mut terminal)
+                .await
+                .expect("could not open pagerduty client")
+                .with_dry_run(dry_run);
+            let db = load_database(&client, &config, &[], None).await;
+
+            swap::run(&client, &db, &mut terminal, tz, days, schedule.as_deref()).await;
+        }
+        Commands::Vacation {
+            at,
+            cover,
+            time_zone,
+            dry_run,
+        } => {
+            let profile = open_profile(&cli.profile);
+            let tz = resolve_time_zone(time_zone, &profile, &config);
+            let (from, to) = parse_range(&tz, &at, &config);
 
-            let client = Client::new().expect("could not open pagerduty client");
-            let db = persistence::Database::load(&client)
+            let client = Client::new(profile, &config.http(), &mut terminal)
                 .await
-                .expect("could not load database");
+                .expect("could not open pagerduty client")
+                .with_dry_run(dry_run);
+            let db = load_database(&client, &config, &[], None).await;
 
-            let mut users_by_email = HashMap::new();
-            db.storage.users.iter().for_each(|u| {
-                users_by_email.insert(u.email.clone(), u);
-            });
+            vacation::run(&client, &db, &mut terminal, tz, from, to, cover).await;
+        }
+        Commands::Who {
+            at,
+            schedule,
+            time_zone,
+        } => {
+            let profile = open_profile(&cli.profile);
+            let tz = resolve_time_zone(time_zone, &profile, &config);
+            let at = parse_instant(&tz, at.as_deref().unwrap_or("now"));
 
-            let current_user;
-            let selected_user = if me {
-                current_user = client.get_me().await.unwrap();
-                &current_user
+            let client = Client::new(profile, &config.http(), &mut terminal)
+                .await
+                .expect("could not open pagerduty client");
+            let db = load_database(&client, &config, &[], None).await;
+
+            let schedule = if schedule.is_empty() {
+                config.schedules.clone()
             } else {
-                fuzzyselect::select(&users_by_email).expect("could not read it")
+                schedule
             };
-
-            let mut schedules_by_name = HashMap::new();
-            db.storage.schedules.iter().for_each(|s| {
-                schedules_by_name.insert(s.name.clone(), s);
-            });
-            let selected_schedule =
-                fuzzyselect::select(&schedules_by_name).expect("could not read it");
-
-            println!("will create override on user {selected_user} for schedule {selected_schedule} from {from} to {to}, confirm to continue.");
-            if confirm() {
-                client
-                    .create_schedule_override(selected_user, selected_schedule, from, to)
-                    .await
-                    .expect("could not create override");
-                println!("Override created! Good luck! ")
-            }
+            who::run(&client, &db, tz, at, &schedule).await;
         }
         Commands::ResetApiKey {} => {
             println!(
                 "About to clear pagerduty API key. This is not reversible, confirm to continue"
             );
 
-            if confirm() {
-                if let Err(err) = Client::clear_api_key() {
+            if Terminal::default().confirm() {
+                let profile = open_profile(&cli.profile);
+                if let Err(err) = Client::clear_api_key(&profile) {
                     eprintln!("could not clear api key: {:?}", err);
                     std::process::exit(1);
                 }
+                // the next key may be of another account
+                if let Err(err) = profile.clear_cache() {
+                    eprintln!("could not clear cached users and schedules: {err}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Whoami {} => {
+            let client = Client::new(open_profile(&cli.profile), &config.http(), &mut terminal)
+                .await
+                .expect("could not open pagerduty client");
+            let me = client.get_me().await.unwrap_or_else(|e| {
+                eprintln!("could not look up the API key's user: {e}");
+                std::process::exit(1);
+            });
+
+            let profile = client.profile();
+            println!("profile: {}", profile.name);
+            println!("user:    {me}");
+            println!("region:  {}", profile.region);
+            println!("auth:    {} from {}", profile.auth, client.source());
+        }
+        Commands::Profile { command } => match command {
+            ProfileCommands::List {} => {
+                let rows: Vec<Vec<String>> = Profile::list()
+                    .expect("could not load profiles")
+                    .into_iter()
+                    .map(|p| {
+                        let current = if p.name == cli.profile { "*" } else { "" };
+                        vec![
+                            current.to_string(),
+                            p.name,
+                            p.region.to_string(),
+                            p.auth.to_string(),
+                            p.time_zone.unwrap_or_default(),
+                            p.from_email.unwrap_or_default(),
+                        ]
+                    })
+                    .collect();
+
+                table::print(&["", "name", "region", "auth", "time zone", "from"], &rows);
+            }
+            ProfileCommands::Add {
+                name,
+                region,
+                time_zone,
+                auth,
+                from,
+                token_command,
+                token_file,
+            } => {
+                if let Err(e) = Profile::check_name(&name) {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                }
+                if Profile::exists(&name).expect("could not load profiles") {
+                    eprintln!("there already is a profile named {name}");
+                    std::process::exit(1);
+                }
+
+                if let Some(tz) = &time_zone {
+                    check_time_zone(tz);
+                }
+
+                let profile = Profile {
+                    region: region.unwrap_or_default(),
+                    time_zone,
+                    auth: auth.unwrap_or_default(),
+                    from_email: from,
+                    token_command,
+                    token_file,
+                    ..Profile::new(&name)
+                };
+                profile.save().expect("could not save profile");
+
+                println!("added profile {name}, use it with --profile {name} to enter its API key");
+            }
+            ProfileCommands::Set {
+                region,
+                time_zone,
+                auth,
+                from,
+                token_command,
+                token_file,
+            } => {
+                let mut profile = open_profile(&cli.profile);
+                if let Some(region) = region {
+                    profile.region = region;
+                }
+                if let Some(tz) = time_zone {
+                    check_time_zone(&tz);
+                    profile.time_zone = Some(tz);
+                }
+                if let Some(auth) = auth {
+                    profile.auth = auth;
+                }
+                if let Some(from) = from {
+                    profile.from_email = Some(from);
+                }
+                if let Some(token_command) = token_command {
+                    profile.token_command = Some(token_command);
+                }
+                if let Some(token_file) = token_file {
+                    profile.token_file = Some(token_file);
+                }
+                profile.save().expect("could not save profile");
+
+                println!("updated profile {}", profile.name);
+            }
+            ProfileCommands::Remove { name } => {
+                let profile = open_profile(&name);
+                if profile.is_default() {
+                    eprintln!("the default profile can't be removed, use reset-api-key instead");
+                    std::process::exit(1);
+                }
+
+                println!("About to remove profile {name} with its API key, cache, aliases and history, confirm to continue");
+                if Terminal::default().confirm() {
+                    profile.remove().await.expect("could not remove profile");
+                }
+            }
+        },
+        Commands::Alias { command } => {
+            let client = Client::new(open_profile(&cli.profile), &config.http(), &mut terminal)
+                .await
+                .expect("could not open pagerduty client");
+            let mut db = load_database(&client, &config, &[], None).await;
+
+            match command {
+                AliasCommands::List {} => aliases::list(&db),
+                AliasCommands::Add {
+                    name,
+                    user,
+                    schedule,
+                } => aliases::add(&mut db, &name, &user, &schedule),
+                AliasCommands::Rm { name } => aliases::remove(&mut db, &name),
             }
         }
+        Commands::Config { command } => match command {
+            ConfigCommands::Show {} => {
+                print!("{}", config.to_toml().expect("could not show config"));
+            }
+            ConfigCommands::Get { key } => match config.get(&key) {
+                Ok(Some(value)) => println!("{value}"),
+                Ok(None) => println!("{key} is not set"),
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                }
+            },
+            ConfigCommands::Set { key, value } => {
+                if config_unreadable {
+                    eprintln!("not saving over a config file that can't be read, fix or remove it");
+                    std::process::exit(1);
+                }
+                let config = config.set(&key, &value).unwrap_or_else(|e| {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                });
+                config.save().expect("could not save config");
+            }
+        },
         Commands::ResetStorage {} => {
             todo!("");
         }
+        Commands::Preview {
+            schedule_id,
+            time_zone,
+        } => {
+            let profile = open_profile(&cli.profile);
+            let tz = resolve_time_zone(time_zone, &profile, &config);
+            if preview::show_cached(&profile.name, &schedule_id, tz) {
+                return;
+            }
+
+            let client = Client::new(profile, &config.http(), &mut terminal)
+                .await
+                .expect("could not open pagerduty client");
+            preview::run(&client, &schedule_id, tz).await;
+        }
     }
 }
 
-fn confirm() -> bool {
-    let sin = io::stdin();
-    let mut answer = String::new();
-    loop {
-        print!("y(es)/n(o)? ");
-        io::stdout().flush().expect("could not flush stdout");
+fn open_profile(name: &str) -> Profile {
+    Profile::load(name).unwrap_or_else(|e| {
+        eprintln!("could not load profile: {e}");
+        std::process::exit(1);
+    })
+}
 
-        answer.clear();
-        sin.read_line(&mut answer).expect("could not read stdin");
+fn check_time_zone(tz: &str) {
+    tz.parse::<chrono_tz::Tz>().unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    });
+}
 
-        let trimmed = answer.trim();
-        if trimmed == "y" || trimmed == "yes" {
-            return true;
-        }
+/// Load the database offering the users and schedules of `teams`. With `prefer_my_teams` the
+/// current user's teams come first, `me` saves looking them up again.
+async fn load_database<'a>(
+    client: &'a Client,
+    config: &Config,
+    teams: &[String],
+    me: Option<&User>,
+) -> persistence::Database<'a> {
+    let mut db = persistence::Database::load(client, config.cache_ttl(), teams)
+        .await
+        .expect("could not load database");
 
-        if trimmed == "n" || trimmed == "no" {
-            return false;
+    if config.prefer_my_teams {
+        let my_teams = match me {
+            Some(me) => Some(me.teams.clone()),
+            None => client
+                .get_me()
+                .await
+                .map(|me| me.teams)
+                .map_err(|e| eprintln!("could not look up your teams: {e}"))
+                .ok(),
+        };
+        if let Some(teams) = my_teams {
+            db.my_teams = teams.into_iter().map(|t| t.id).collect();
         }
+    }
+
+    db
+}
+
+/// How the finder calls back into this binary for previews, with the same profile, files and time
+/// zone.
+fn preview_command(cli: &Cli) -> Option<String> {
+    let exe = std::env::current_exe().ok()?;
 
-        println!("got {}", trimmed);
+    let mut args = vec![exe.to_string_lossy().to_string()];
+    args.extend([String::from("--profile"), cli.profile.clone()]);
+    if let Some(data_dir) = &cli.data_dir {
+        args.extend([
+            String::from("--data-dir"),
+            data_dir.to_string_lossy().to_string(),
+        ]);
+    }
+    args.push(String::from("preview"));
+    if let Some(tz) = cli.command.time_zone() {
+        args.extend([String::from("--time-zone"), tz.to_string()]);
     }
+
+    let quoted: Vec<String> = args
+        .iter()
+        .map(|arg| format!("'{}'", arg.replace('\'', r"'\''")))
+        .collect();
+    Some(quoted.join(" "))
+}
+
+/// IDs of the teams whose users and schedules are offered: the ones given by name or ID, or else
+/// those of `me`, the current user. None, for all of them, with `all_teams` or without `me`.
+async fn resolve_teams(
+    client: &Client,
+    names: &[String],
+    all_teams: bool,
+    me: Option<&User>,
+) -> Vec<String> {
+    if all_teams {
+        return Vec::new();
+    }
+
+    if names.is_empty() {
+        let Some(me) = me else {
+            return Vec::new();
+        };
+        if !me.teams.is_empty() {
+            let names: Vec<&str> = me.teams.iter().map(|t| t.summary.as_str()).collect();
+            println!(
+                "offering users and schedules of {}, use --all-teams for everyone",
+                names.join(", ")
+            );
+        }
+        return me.teams.iter().map(|t| t.id.clone()).collect();
+    }
+
+    let teams = client.get_teams().await.unwrap_or_else(|e| {
+        eprintln!("could not fetch teams: {e}");
+        std::process::exit(1);
+    });
+    names
+        .iter()
+        .map(|name| {
+            match teams
+                .iter()
+                .find(|t| t.id == *name || t.name.eq_ignore_ascii_case(name))
+            {
+                Some(team) => team.id.clone(),
+                None => {
+                    eprintln!("no team is called {name}");
+                    std::process::exit(1);
+                }
+            }
+        })
+        .collect()
+}
+
+fn resolve_time_zone(
+    time_zone: Option<String>,
+    profile: &Profile,
+    config: &Config,
+) -> chrono_tz::Tz {
+    let tz_string = time_zone
+        .or_else(|| profile.time_zone.clone())
+        .or_else(|| config.time_zone.clone())
+        .unwrap_or_else(|| {
+            iana_time_zone::get_timezone().unwrap_or_else(|e| {
+                eprintln!("could not find your time zone, give one with --time-zone: {e}");
+                std::process::exit(1);
+            })
+        });
+
+    tz_string.parse().unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    })
+}
+
+fn parse_range(
+    tz: &chrono_tz::Tz,
+    at: &str,
+    config: &Config,
+) -> (DateTime<chrono_tz::Tz>, DateTime<chrono_tz::Tz>) {
+    let now = chrono::Utc::now().timestamp();
+    timeparse::parse(&tz.timestamp_opt(now, 0).unwrap(), at, &config.workday).unwrap_or_else(|e| {
+        eprintln!("could not parse the time given time range: {:?}", e);
+        eprintln!("here are some example time ranges: ");
+
+        for example in timeparse::VALID_TIMES {
+            eprintln!("\t{example}");
+        }
+
+        std::process::exit(1);
+    })
+}
+
+fn parse_instant(tz: &chrono_tz::Tz, at: &str) -> DateTime<chrono_tz::Tz> {
+    let now = chrono::Utc::now().timestamp();
+    timeparse::parse_instant(&tz.timestamp_opt(now, 0).unwrap(), at).unwrap_or_else(|e| {
+        eprintln!("could not parse the given time: {:?}", e);
+        eprintln!("here are some example times: ");
+
+        for example in timeparse::VALID_INSTANTS {
+            eprintln!("\t{example}");
+        }
+
+        std::process::exit(1);
+    })
 }
@@ -0,0 +1,270 @@
+//! A fake pagerduty API for tests. It speaks just enough HTTP/1.1 for reqwest, serves users and
+//! schedules in small pages so pagination gets exercised, and records every request it receives.
+
+use std::sync::{Arc, Mutex};
+
+use reqwest::Url;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.body).expect("request body is not json")
+    }
+}
+
+/// The state behind the fake API.
+pub struct FakePagerduty {
+    pub users: Vec<Value>,
+    pub schedules: Vec<Value>,
+    pub me: Value,
+    pub oncalls: Vec<Value>,
+    pub teams: Vec<Value>,
+    /// The most items returned per page, whatever limit the client asks for.
+    pub page_size: usize,
+    /// How many of the next requests get rejected with a 429.
+    pub rate_limited: usize,
+    /// Paths rejected with a 403, like a token without the scope for them.
+    pub forbidden: Vec<&'static str>,
+    /// Whether new overrides are refused one by one, like pagerduty does with invalid ones.
+    pub rejecting_overrides: bool,
+    /// Overrides with the id of the schedule they are on.
+    pub overrides: Vec<(String, Value)>,
+}
+
+impl FakePagerduty {
+    /// Users and schedules from `fake_storage.json`, with the first user as the current user.
+    pub fn from_fixture() -> FakePagerduty {
+        let fixture: Value = serde_json::from_str(include_str!("../fake_storage.json")).unwrap();
+        let users = fixture["users"].as_array().unwrap().clone();
+
+        FakePagerduty {
+            me: users[0].clone(),
+            users,
+            schedules: fixture["schedules"].as_array().unwrap().clone(),
+            oncalls: Vec::new(),
+            teams: Vec::new(),
+            page_size: 2,
+            rate_limited: 0,
+            forbidden: Vec::new(),
+            rejecting_overrides: false,
+            overrides: Vec::new(),
+        }
+    }
+
+    fn handle(&mut self, req: &Request) -> (u16, Value) {
+        if self.rate_limited > 0 {
+            self.rate_limited -= 1;
+            return (
+                429,
+                json!({ "error": { "message": "Rate Limit Exceeded" } }),
+            );
+        }
+
+        if self.forbidden.contains(&req.path.as_str()) {
+            return (
+                403,
+                json!({ "error": { "message": "Access Denied", "errors": ["Missing scope"] } }),
+            );
+        }
+
+        let segments: Vec<&str> = req.path.trim_matches('/').split('/').collect();
+        match (req.method.as_str(), segments.as_slice()) {
+            ("GET", ["users", "me"]) => (200, json!({ "user": self.me })),
+            ("GET", ["users"]) => (200, self.page(req, "users", &self.users)),
+            ("GET", ["schedules"]) => (200, self.page(req, "schedules", &self.schedules)),
+            ("GET", ["oncalls"]) => (200, self.page(req, "oncalls", &self.oncalls)),
+            ("GET", ["teams"]) => (200, self.page(req, "teams", &self.teams)),
+            ("GET", ["schedules", id]) => (
+                200,
+                json!({
+                    "schedule": {
+                        "id": id,
+                        "final_schedule": { "rendered_schedule_entries": [] },
+                    }
+                }),
+            ),
+            ("GET", ["schedules", id, "overrides"]) => {
+                let overrides: Vec<&Value> = self
+                    .overrides
+                    .iter()
+                    .filter(|(schedule, _)| schedule == id)
+                    .map(|(_, o)| o)
+                    .collect();
+                (200, json!({ "overrides": overrides }))
+            }
+            ("POST", ["schedules", id, "overrides"]) => {
+                let mut results = Vec::new();
+                for o in req.json()["overrides"].as_array().unwrap() {
+                    if self.rejecting_overrides {
+                        results.push(json!({ "status": 400, "errors": ["Invalid override"] }));
+                        continue;
+                    }
+                    let mut created = o.clone();
+                    created["id"] = json!(format!("PO{}", self.overrides.len()));
+                    self.overrides.push((id.to_string(), created.clone()));
+                    results.push(json!({ "status": 201, "override": created }));
+                }
+                (201, json!(results))
+            }
+            ("DELETE", ["schedules", id, "overrides", override_id]) => {
+                self.overrides
+                    .retain(|(schedule, o)| !(schedule == id && o["id"] == *override_id));
+                (204, Value::Null)
+            }
+            _ => (404, json!({ "error": { "message": "Not Found" } })),
+        }
+    }
+
+    fn page(&self, req: &Request, key: &str, items: &[Value]) -> Value {
+        let offset: usize = req.query_param("offset").map_or(0, |o| o.parse().unwrap());
+        let limit: usize = req.query_param("limit").map_or(25, |l| l.parse().unwrap());
+        let limit = limit.min(self.page_size);
+
+        let end = (offset + limit).min(items.len());
+        json!({
+            key: items[offset.min(end)..end],
+            "more": end < items.len(),
+            "limit": limit,
+            "offset": offset,
+            "total": items.len(),
+        })
+    }
+}
+
+pub struct MockServer {
+    pub url: String,
+    state: Arc<Mutex<State>>,
+}
+
+struct State {
+    fake: FakePagerduty,
+    requests: Vec<Request>,
+}
+
+impl MockServer {
+    pub async fn start(fake: FakePagerduty) -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State {
+            fake,
+            requests: Vec::new(),
+        }));
+
+        let server_state = state.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(serve(stream, server_state.clone()));
+            }
+        });
+
+        MockServer { url, state }
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+async fn serve(mut stream: TcpStream, state: Arc<Mutex<State>>) {
+    let Some(req) = read_request(&mut stream).await else {
+        return;
+    };
+
+    let (status, body) = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(req.clone());
+        state.fake.handle(&req)
+    };
+
+    let body = if body.is_null() {
+        String::new()
+    } else {
+        body.to_string()
+    };
+    let retry_after = if status == 429 {
+        "retry-after: 0\r\n"
+    } else {
+        ""
+    };
+    let response = format!(
+        "HTTP/1.1 {status} MOCK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n{retry_after}connection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await.unwrap();
+    stream.shutdown().await.unwrap();
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_string();
+    let target = Url::parse(&format!("http://mock{}", request_line.next()?)).ok()?;
+
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+        .collect();
+    let content_length: usize = headers
+        .iter()
+        .find(|(k, _)| k == "content-length")
+        .map_or(0, |(_, v)| v.parse().unwrap());
+
+    let mut body = buf[header_end + 4..].to_vec();
+    while body.len() < content_length {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+
+    Some(Request {
+        method,
+        path: target.path().to_string(),
+        query: target.query_pairs().into_owned().collect(),
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
@@ -0,0 +1,208 @@
+//! Where files are kept. The cache of users and schedules goes in the platform's cache directory,
+//! profiles and other settings in its config directory and the history of selections in its state
+//! directory (`$XDG_CACHE_HOME`, `$XDG_CONFIG_HOME` and `$XDG_STATE_HOME` on Linux). A data
+//! directory given with `--data-dir` or `PD_QUICK_OVERRIDE_DATA_DIR` holds all of them instead.
+
+use std::{
+    error::Error,
+    io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+pub const DATA_DIR_VAR: &str = "PD_QUICK_OVERRIDE_DATA_DIR";
+
+const APP_DIR: &str = "pd-quick-override";
+
+/// Where everything was kept before the platform directories were used.
+const LEGACY_DIR: &str = ".pd-quick-override";
+
+static DATA_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Settle where files go before anything reads or writes them, moving files over from the legacy
+/// directory when the platform directories are used.
+pub fn init(data_dir: Option<PathBuf>) {
+    let overridden = data_dir.is_some();
+    DATA_DIR
+        .set(data_dir)
+        .expect("paths are only initialized once");
+
+    if !overridden {
+        if let Err(e) = migrate_legacy_dir() {
+            eprintln!("could not move files out of ~/{LEGACY_DIR}: {e}");
+        }
+    }
+}
+
+/// The cached users and schedules of every profile.
+pub fn cache_dir() -> Result<PathBuf, Box<dyn Error>> {
+    resolve(dirs::cache_dir())
+}
+
+/// Profiles and settings.
+pub fn config_dir() -> Result<PathBuf, Box<dyn Error>> {
+    resolve(dirs::config_dir())
+}
+
+/// History kept between runs. Platforms without a state directory keep it with local data.
+pub fn state_dir() -> Result<PathBuf, Box<dyn Error>> {
+    resolve(dirs::state_dir().or_else(dirs::data_local_dir))
+}
+
+fn resolve(platform_dir: Option<PathBuf>) -> Result<PathBuf, Box<dyn Error>> {
+    if let Some(data_dir) = DATA_DIR.get().cloned().flatten() {
+        return Ok(data_dir);
+    }
+
+    match platform_dir {
+        Some(dir) => Ok(dir.join(APP_DIR)),
+        None => Err(Box::from(format!(
+            "could not find a home directory, choose where to keep files with --data-dir or \
+             {DATA_DIR_VAR}"
+        ))),
+    }
+}
+
+fn migrate_legacy_dir() -> Result<(), Box<dyn Error>> {
+    let Some(legacy_dir) = dirs::home_dir().map(|home| home.join(LEGACY_DIR)) else {
+        return Ok(());
+    };
+    if !legacy_dir.is_dir() {
+        return Ok(());
+    }
+
+    let moved = migrate(&legacy_dir, &cache_dir()?, &config_dir()?)?;
+    if moved > 0 {
+        eprintln!(
+            "moved {moved} file(s) from {} to {} and {}",
+            legacy_dir.display(),
+            cache_dir()?.display(),
+            config_dir()?.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Move profiles and aliases to `config_dir` and caches to `cache_dir`, removing `legacy_dir` once
+/// it is empty. Files that already exist in the new place are left alone, and so is anything else,
+/// like lock files or caches half written by a run that is still going.
+fn migrate(legacy_dir: &Path, cache_dir: &Path, config_dir: &Path) -> io::Result<usize> {
+    let mut moved = 0;
+    for entry in std::fs::read_dir(legacy_dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+
+        let name = entry.file_name();
+        let to_dir = match name.to_str() {
+            Some("profiles.json" | "aliases.json") => config_dir,
+            Some(name) if name.starts_with("storage") && name.ends_with(".json") => cache_dir,
+            _ => continue,
+        };
+        let to = to_dir.join(&name);
+        if to.exists() {
+            continue;
+        }
+
+        std::fs::create_dir_all(to_dir)?;
+        move_file(&entry.path(), &to)?;
+        moved += 1;
+    }
+
+    if std::fs::read_dir(legacy_dir)?.next().is_none() {
+        std::fs::remove_dir(legacy_dir)?;
+    }
+
+    Ok(moved)
+}
+
+/// Rename `from` to `to`, copying when they are on different file systems.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    std::fs::copy(from, to)?;
+    std::fs::remove_file(from)
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn test_migrate() {
+        let home = tempfile::tempdir().unwrap();
+        let legacy_dir = home.path().join(LEGACY_DIR);
+        let cache_dir = home.path().join(".cache").join(APP_DIR);
+        let config_dir = home.path().join(".config").join(APP_DIR);
+
+        std::fs::create_dir_all(&legacy_dir).unwrap();
+        std::fs::write(legacy_dir.join("storage.json"), "cached").unwrap();
+        std::fs::write(legacy_dir.join("storage-work.json"), "cached work").unwrap();
+        std::fs::write(legacy_dir.join("profiles.json"), "profiles").unwrap();
+        std::fs::write(legacy_dir.join("aliases.json"), "aliases").unwrap();
+
+        assert_eq!(migrate(&legacy_dir, &cache_dir, &config_dir).unwrap(), 4);
+
+        assert!(!legacy_dir.exists());
+        assert_eq!(
+            std::fs::read_to_string(cache_dir.join("storage.json")).unwrap(),
+            "cached"
+        );
+        assert_eq!(
+            std::fs::read_to_string(cache_dir.join("storage-work.json")).unwrap(),
+            "cached work"
+        );
+        assert_eq!(
+            std::fs::read_to_string(config_dir.join("profiles.json")).unwrap(),
+            "profiles"
+        );
+        assert_eq!(
+            std::fs::read_to_string(config_dir.join("aliases.json")).unwrap(),
+            "aliases"
+        );
+    }
+
+    #[test]
+    fn test_migrate_leaves_other_files() {
+        let home = tempfile::tempdir().unwrap();
+        let legacy_dir = home.path().join(LEGACY_DIR);
+        let cache_dir = home.path().join("cache");
+        let config_dir = home.path().join("config");
+
+        std::fs::create_dir_all(&legacy_dir).unwrap();
+        std::fs::write(legacy_dir.join("storage.json"), "cached").unwrap();
+        std::fs::write(legacy_dir.join("storage.json.lock"), "").unwrap();
+        std::fs::write(legacy_dir.join("storage.json.tmp"), "half").unwrap();
+
+        assert_eq!(migrate(&legacy_dir, &cache_dir, &config_dir).unwrap(), 1);
+
+        assert!(legacy_dir.join("storage.json.lock").exists());
+        assert!(legacy_dir.join("storage.json.tmp").exists());
+        assert!(!cache_dir.join("storage.json.lock").exists());
+    }
+
+    #[test]
+    fn test_migrate_keeps_newer_files() {
+        let home = tempfile::tempdir().unwrap();
+        let legacy_dir = home.path().join(LEGACY_DIR);
+        let cache_dir = home.path().join("cache");
+        let config_dir = home.path().join("config");
+
+        std::fs::create_dir_all(&legacy_dir).unwrap();
+        std::fs::create_dir_all(&cache_dir).unwrap();
+        std::fs::write(legacy_dir.join("storage.json"), "old").unwrap();
+        std::fs::write(cache_dir.join("storage.json"), "new").unwrap();
+
+        assert_eq!(migrate(&legacy_dir, &cache_dir, &config_dir).unwrap(), 0);
+
+        assert!(legacy_dir.join("storage.json").exists());
+        assert_eq!(
+            std::fs::read_to_string(cache_dir.join("storage.json")).unwrap(),
+            "new"
+        );
+    }
+}
@@ -1,54 +1,517 @@
-use std::{error::Error, path::PathBuf};
+use std::{
+    cell::RefCell,
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+    ffi::OsString,
+    fmt::Display,
+    fs::{File, TryLockError},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
 
-use chrono::Utc;
+use chrono::{Duration, Utc};
 
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use serde::{Deserialize, Serialize};
-use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
-    join,
+use serde_json::{json, Value};
+use tokio::{io::AsyncReadExt, join};
+
+use crate::{
+    client::{self, Schedule, User},
+    paths,
+    profile::DEFAULT_PROFILE,
+    prompt::Prompt,
 };
 
-use crate::client::{self, Schedule, User};
+/// The version of the cache format written by this build. Bump it and teach `migrate` to upgrade
+/// the previous version whenever `Serialized` changes in a way old files can't be read as.
+const STORAGE_VERSION: u64 = 2;
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Serialized {
+    pub version: u64,
+    /// The profile the users and schedules were loaded with.
+    pub profile: String,
+    /// The account they were loaded from, unknown for caches from older versions.
+    #[serde(default)]
+    pub account: Option<String>,
     pub users: Vec<User>,
     pub schedules: Vec<Schedule>,
     pub updated_at: i64, // in seconds
 }
 
+/// A short name for one or more users or schedules, kept by ID.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Alias {
+    Users(Vec<String>),
+    Schedules(Vec<String>),
+}
+
+/// How often and how recently something was selected.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    pub count: u32,
+    pub last_used: i64, // in seconds
+}
+
+impl Usage {
+    /// Selections count for less the longer ago the last one was, so a schedule picked every day
+    /// last year gives way to one picked a few times this week.
+    fn score(&self, now: i64) -> u64 {
+        let weight = match (now - self.last_used).max(0) / (24 * 60 * 60) {
+            0..=3 => 100,
+            4..=13 => 70,
+            14..=30 => 50,
+            31..=90 => 30,
+            _ => 10,
+        };
+
+        u64::from(self.count) * weight
+    }
+}
+
+/// The users and schedules selected with a profile, by ID.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct History {
+    pub users: BTreeMap<String, Usage>,
+    pub schedules: BTreeMap<String, Usage>,
+}
+
 pub struct Database<'a> {
     client: &'a client::Client,
+    storage_file: PathBuf,
     pub storage: Serialized,
+    /// Aliases of the client's profile. Unlike the cache they are never fetched again, so they
+    /// live with the settings.
+    pub aliases: BTreeMap<String, Alias>,
+    /// What was selected before, for offering it first. Selections are recorded while picking,
+    /// which only borrows the database.
+    history: RefCell<History>,
+    /// Where the history is saved, `None` for a database that doesn't keep it.
+    history_file: Option<PathBuf>,
+    /// Whether the cache file is left alone, because a newer build wrote it.
+    read_only: bool,
+    /// IDs of the teams whose users and schedules are offered for selection, every team's when
+    /// empty. Anything can still be picked by name.
+    teams: Vec<String>,
+    /// IDs of the current user's teams. Their users and schedules are offered before others that
+    /// were selected as often.
+    pub my_teams: Vec<String>,
 }
 
 impl<'a> Database<'a> {
-    pub async fn load(client: &'a client::Client) -> Result<Database<'a>, Box<dyn Error>> {
-        let storage_dir = Self::get_storage_dir();
-        if !storage_dir.exists() {
-            std::fs::create_dir_all(storage_dir).expect("could not create directory");
-        }
+    pub async fn load(
+        client: &'a client::Client,
+        cache_ttl: Option<Duration>,
+        teams: &[String],
+    ) -> Result<Database<'a>, Box<dyn Error>> {
+        let mut db = Self::load_from(client, paths::cache_dir()?, cache_ttl, teams).await?;
+
+        let aliases_file = paths::config_dir()?.join("aliases.json");
+        db.aliases = load_all_aliases(&aliases_file)?
+            .remove(&client.profile().name)
+            .unwrap_or_default();
+
+        let history_file = paths::state_dir()?.join("history.json");
+        db.history = RefCell::new(
+            load_all_history(&history_file)?
+                .remove(&client.profile().name)
+                .unwrap_or_default(),
+        );
+        db.history_file = Some(history_file);
 
-        let storage_file = Self::get_storage_file();
+        Ok(db)
+    }
+
+    /// Load the database cached in `storage_dir`, fetching everything from pagerduty if there is no
+    /// cache there yet or it is older than `cache_ttl`. Everything is cached whatever the `teams`,
+    /// which only limit what is offered for selection.
+    pub async fn load_from(
+        client: &'a client::Client,
+        storage_dir: PathBuf,
+        cache_ttl: Option<Duration>,
+        teams: &[String],
+    ) -> Result<Database<'a>, Box<dyn Error>> {
+        std::fs::create_dir_all(&storage_dir)?;
+
+        let storage_file = storage_dir.join(client.profile().storage_file_name());
+        let _lock = lock(&storage_file).await?;
         let mut db = Database {
             client,
+            storage_file: storage_file.clone(),
             storage: Serialized {
+                version: STORAGE_VERSION,
+                profile: client.profile().name.clone(),
+                account: None,
                 users: Vec::new(),
                 schedules: Vec::new(),
                 updated_at: 0,
             },
+            aliases: BTreeMap::new(),
+            history: RefCell::default(),
+            history_file: None,
+            read_only: false,
+            teams: teams.to_vec(),
+            my_teams: Vec::new(),
         };
         if !storage_file.exists() {
             db.do_remote_load().await?;
             return Ok(db);
-        } else {
-            db.do_file_load().await?;
+        }
+
+        // a cache that can't be read is only a cache, start over instead of failing
+        if let Err(e) = db.do_file_load().await {
+            eprintln!(
+                "could not read cached users and schedules from {}: {e}",
+                storage_file.display()
+            );
+            if e.is::<serde_json::Error>() {
+                let quarantined = quarantine(&storage_file)?;
+                eprintln!("moved the corrupt cache to {}", quarantined.display());
+            }
+            // a newer build can still read it, so only this run goes without a cache
+            db.read_only = e.is::<NewerCache>();
+            db.do_remote_load().await?;
+            return Ok(db);
+        }
+
+        // never offer users or schedules of one account to another
+        let profile = db.client.profile();
+        if db.storage.profile != profile.name {
+            eprintln!(
+                "{} holds users and schedules of profile {}, not {}",
+                storage_file.display(),
+                db.storage.profile,
+                profile.name
+            );
+            db.do_remote_load().await?;
+            return Ok(db);
+        }
+
+        // the profile's API key may be of another account than when the cache was loaded
+        let account = db.client.account().await?;
+        match &db.storage.account {
+            Some(cached) if cached != account => {
+                eprintln!(
+                    "{} holds users and schedules of account {cached}, not {account}",
+                    storage_file.display()
+                );
+                db.do_remote_load().await?;
+                return Ok(db);
+            }
+            Some(_) => {}
+            // older caches don't say, so they are taken to be of the current account
+            None => {
+                db.storage.account = Some(account.to_string());
+                db.write_to_disk().await?;
+            }
+        }
+
+        let age = Duration::seconds(Utc::now().timestamp() - db.storage.updated_at);
+        if cache_ttl.is_some_and(|ttl| age > ttl) {
+            println!(
+                "cached users and schedules are {} hours old",
+                age.num_hours()
+            );
+            db.do_remote_load().await?;
+        } else if !teams.is_empty() && !db.knows_teams() {
+            println!("cached users and schedules don't know their teams");
+            db.do_remote_load().await?;
         }
 
         Ok(db)
     }
 
+    /// Whether the teams of users and schedules are known, caches from older versions don't have
+    /// them.
+    fn knows_teams(&self) -> bool {
+        self.storage.users.iter().any(|u| !u.teams.is_empty())
+            || self.storage.schedules.iter().any(|s| !s.teams.is_empty())
+    }
+
+    /// Whether something on `teams` is offered for selection.
+    fn offered(&self, teams: &[client::Reference]) -> bool {
+        self.teams.is_empty() || teams.iter().any(|t| self.teams.contains(&t.id))
+    }
+
+    /// Users to select from, aliases of a single user first and then the most frequently and
+    /// recently selected of the offered teams.
+    pub fn user_options(&self) -> Vec<(String, &User)> {
+        let aliased = self.aliases.iter().filter_map(|(name, alias)| match alias {
+            Alias::Users(ids) if ids.len() == 1 => self.user_by_id(&ids[0]).map(|u| (name, u)),
+            _ => None,
+        });
+
+        let mut options: Vec<(String, &User)> = aliased
+            .map(|(name, u)| (format!("@{name} {}", u.label()), u))
+            .collect();
+        let mut users: Vec<(String, &User)> = self
+            .storage
+            .users
+            .iter()
+            .filter(|u| self.offered(&u.teams))
+            .map(|u| (u.label(), u))
+            .collect();
+        disambiguate(&mut users, |u| &u.id);
+        self.rank(&mut users, &self.history.borrow().users, |u| {
+            (&u.id, &u.teams)
+        });
+        options.extend(users);
+
+        options
+    }
+
+    /// Schedules to select from, aliases of a single schedule first and then the most frequently
+    /// and recently selected of the offered teams.
+    pub fn schedule_options(&self) -> Vec<(String, &Schedule)> {
+        let aliased = self.aliases.iter().filter_map(|(name, alias)| match alias {
+            Alias::Schedules(ids) if ids.len() == 1 => {
+                self.schedule_by_id(&ids[0]).map(|s| (name, s))
+            }
+            _ => None,
+        });
+
+        let mut options: Vec<(String, &Schedule)> = aliased
+            .map(|(name, s)| (format!("@{name} {}", s.label()), s))
+            .collect();
+        let mut schedules: Vec<(String, &Schedule)> = self
+            .storage
+            .schedules
+            .iter()
+            .filter(|s| self.offered(&s.teams))
+            .map(|s| (s.label(), s))
+            .collect();
+        disambiguate(&mut schedules, |s| &s.id);
+        self.rank(&mut schedules, &self.history.borrow().schedules, |s| {
+            (&s.id, &s.teams)
+        });
+        options.extend(schedules);
+
+        options
+    }
+
+    /// Order `options` by frecency, then those of the current user's teams first, then by label.
+    fn rank<T>(
+        &self,
+        options: &mut [(String, &T)],
+        usage: &BTreeMap<String, Usage>,
+        id_and_teams: fn(&T) -> (&String, &Vec<client::Reference>),
+    ) {
+        let now = Utc::now().timestamp();
+        options.sort_by_cached_key(|(label, option)| {
+            let (id, teams) = id_and_teams(option);
+            let score = usage.get(id).map_or(0, |u| u.score(now));
+            let mine = teams.iter().any(|t| self.my_teams.contains(&t.id));
+
+            (Reverse(score), !mine, label.clone())
+        });
+    }
+
+    /// The users `name` refers to: every user of an alias, or the user with that email or ID.
+    pub fn find_users(&self, name: &str) -> Vec<&User> {
+        if let Some(Alias::Users(ids)) = self.aliases.get(name.trim_start_matches('@')) {
+            return ids.iter().filter_map(|id| self.user_by_id(id)).collect();
+        }
+
+        self.storage
+            .users
+            .iter()
+            .filter(|u| u.id == name || u.email.eq_ignore_ascii_case(name))
+            .collect()
+    }
+
+    /// The schedules `name` refers to: every schedule of an alias, or the schedules with that ID or
+    /// name.
+    pub fn find_schedules(&self, name: &str) -> Vec<&Schedule> {
+        if let Some(Alias::Schedules(ids)) = self.aliases.get(name.trim_start_matches('@')) {
+            return ids
+                .iter()
+                .filter_map(|id| self.schedule_by_id(id))
+                .collect();
+        }
+
+        self.storage
+            .schedules
+            .iter()
+            .filter(|s| s.id == name || s.name.eq_ignore_ascii_case(name))
+            .collect()
+    }
+
+    /// The schedules matching any of `filters`: those `find_schedules` finds for it, or else those
+    /// whose name contains it.
+    pub fn match_schedules(&self, filters: &[String]) -> Result<Vec<&Schedule>, String> {
+        let mut matches = Vec::new();
+        for filter in filters {
+            let found = self.find_schedules(filter);
+            if !found.is_empty() {
+                matches.extend(found);
+                continue;
+            }
+
+            let lowered = filter.to_lowercase();
+            let before = matches.len();
+            matches.extend(
+                self.storage
+                    .schedules
+                    .iter()
+                    .filter(|s| s.id == *filter || s.name.to_lowercase().contains(&lowered)),
+            );
+
+            if matches.len() == before {
+                return Err(format!("no schedule matches {filter}"));
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// The user `name` refers to, selecting one of them when it is a group alias. Without a name
+    /// any user can be selected.
+    pub async fn pick_user(
+        &self,
+        prompt: &mut impl Prompt,
+        name: Option<&str>,
+    ) -> Result<&User, Box<dyn Error>> {
+        let user = match name {
+            None => prompt.select(&self.user_options())?,
+            Some(name) => match self.find_users(name).as_slice() {
+                [] => return Err(Box::from(format!("no user or alias is called {name}"))),
+                [user] => user,
+                users => {
+                    let mut options: Vec<(String, &User)> =
+                        users.iter().map(|u| (u.label(), *u)).collect();
+                    disambiguate(&mut options, |u| &u.id);
+                    prompt.select(&options)?
+                }
+            },
+        };
+
+        self.record(|h| &mut h.users, &user.id).await;
+        Ok(user)
+    }
+
+    /// The schedule `name` refers to, selecting one of them when it is a group alias. Without a
+    /// name any schedule can be selected.
+    pub async fn pick_schedule(
+        &self,
+        prompt: &mut impl Prompt,
+        name: Option<&str>,
+    ) -> Result<&Schedule, Box<dyn Error>> {
+        let schedule = match name {
+            None => prompt.select_previewed(&self.schedule_options(), |s| &s.id)?,
+            Some(name) => match self.find_schedules(name).as_slice() {
+                [] => return Err(Box::from(format!("no schedule or alias is called {name}"))),
+                [schedule] => schedule,
+                schedules => {
+                    let mut options: Vec<(String, &Schedule)> =
+                        schedules.iter().map(|s| (s.label(), *s)).collect();
+                    disambiguate(&mut options, |s| &s.id);
+                    prompt.select_previewed(&options, |s| &s.id)?
+                }
+            },
+        };
+
+        self.record(|h| &mut h.schedules, &schedule.id).await;
+        Ok(schedule)
+    }
+
+    /// A schedule matching `filters`, like the `schedules` setting, selecting one of them when
+    /// there are several.
+    pub async fn pick_schedule_among(
+        &self,
+        prompt: &mut impl Prompt,
+        filters: &[String],
+    ) -> Result<&Schedule, Box<dyn Error>> {
+        let mut seen = HashSet::new();
+        let mut options: Vec<(String, &Schedule)> = self
+            .match_schedules(filters)?
+            .into_iter()
+            .filter(|s| seen.insert(&s.id))
+            .map(|s| (s.label(), s))
+            .collect();
+
+        let schedule = match options.as_slice() {
+            [(_, schedule)] => *schedule,
+            _ => {
+                disambiguate(&mut options, |s| &s.id);
+                self.rank(&mut options, &self.history.borrow().schedules, |s| {
+                    (&s.id, &s.teams)
+                });
+                prompt.select_previewed(&options, |s| &s.id)?
+            }
+        };
+
+        self.record(|h| &mut h.schedules, &schedule.id).await;
+        Ok(schedule)
+    }
+
+    /// Count a selection of the user or schedule with `id`. Failing to save the history is only
+    /// worth a warning. Dry runs change nothing, so they aren't counted either.
+    async fn record(&self, kind: fn(&mut History) -> &mut BTreeMap<String, Usage>, id: &str) {
+        if self.client.dry_run() {
+            return;
+        }
+
+        {
+            let mut history = self.history.borrow_mut();
+            let usage = kind(&mut history).entry(id.to_string()).or_default();
+            usage.count += 1;
+            usage.last_used = Utc::now().timestamp();
+        }
+
+        if let Err(e) = self.save_history().await {
+            eprintln!("could not save what was selected: {e}");
+        }
+    }
+
+    /// Save the history of this profile, under a lock like the cache. The file holds the history
+    /// of every profile, so runs with other profiles would otherwise write over each other.
+    async fn save_history(&self) -> Result<(), Box<dyn Error>> {
+        let Some(history_file) = &self.history_file else {
+            return Ok(());
+        };
+
+        if let Some(dir) = history_file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let _lock = lock(history_file).await?;
+        let mut all = load_all_history(history_file)?;
+        all.insert(
+            self.client.profile().name.clone(),
+            self.history.borrow().clone(),
+        );
+
+        write_atomically(history_file, serde_json::to_string_pretty(&all)?.as_bytes())?;
+
+        Ok(())
+    }
+
+    fn user_by_id(&self, id: &str) -> Option<&User> {
+        self.storage.users.iter().find(|u| u.id == id)
+    }
+
+    fn schedule_by_id(&self, id: &str) -> Option<&Schedule> {
+        self.storage.schedules.iter().find(|s| s.id == id)
+    }
+
+    /// Save the aliases of the client's profile.
+    pub fn save_aliases(&self) -> Result<(), Box<dyn Error>> {
+        let aliases_file = paths::config_dir()?.join("aliases.json");
+        let mut all = load_all_aliases(&aliases_file)?;
+        all.insert(self.client.profile().name.clone(), self.aliases.clone());
+
+        std::fs::create_dir_all(paths::config_dir()?)?;
+        write_atomically(
+            &aliases_file,
+            serde_json::to_string_pretty(&all)?.as_bytes(),
+        )?;
+
+        Ok(())
+    }
+
     async fn do_remote_load(&mut self) -> Result<(), Box<dyn Error>> {
         println!("loading all users and schedules from Pagerduty. This will take a while, but should only happen once");
 
@@ -70,6 +533,9 @@ impl<'a> Database<'a> {
         let schedules = r_schedules?;
 
         self.storage = Serialized {
+            version: STORAGE_VERSION,
+            profile: self.client.profile().name.clone(),
+            account: Some(self.client.account().await?.to_string()),
             schedules,
             users,
             updated_at: Utc::now().timestamp(),
@@ -80,39 +546,27 @@ impl<'a> Database<'a> {
         Ok(())
     }
 
-    fn get_storage_file() -> PathBuf {
-        let mut dir = Self::get_storage_dir();
-        dir.push("storage.json");
-
-        dir
-    }
-
-    fn get_storage_dir() -> PathBuf {
-        let mut home = dirs::home_dir().expect("could not find home directory");
-        home.push(".pd-quick-override");
-
-        home
-    }
-
     async fn write_to_disk(&self) -> Result<(), Box<dyn Error>> {
-        let mut file = tokio::fs::File::create(Self::get_storage_file()).await?;
+        if self.read_only {
+            return Ok(());
+        }
 
         let jstring = serde_json::to_string(&self.storage)?;
-
-        file.write_all(jstring.as_bytes()).await?;
-        file.flush().await?;
+        write_atomically(&self.storage_file, jstring.as_bytes())?;
 
         Ok(())
     }
 
     pub async fn do_file_load(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let storage_file = Self::get_storage_file();
-        let mut f = tokio::fs::File::open(&storage_file).await?;
+        let storage_file = &self.storage_file;
+        let mut f = tokio::fs::File::open(storage_file).await?;
 
         let mut out = String::new();
         f.read_to_string(&mut out).await?;
 
-        let parsed: Serialized = serde_json::from_str(out.as_str())?;
+        let stored: Value = serde_json::from_str(out.as_str())?;
+        let stored_version = version_of(&stored);
+        let parsed: Serialized = serde_json::from_value(migrate(stored)?)?;
 
         println!(
             "loaded cached schedules and users from {}, last updated at {}",
@@ -122,6 +576,600 @@ impl<'a> Database<'a> {
 
         self.storage = parsed;
 
+        // upgrade the file in place, so the migrations only run once
+        if stored_version != STORAGE_VERSION {
+            self.write_to_disk().await?;
+        }
+
         Ok(())
     }
 }
+
+/// Aliases of every profile, by profile name.
+fn load_all_aliases(
+    aliases_file: &Path,
+) -> Result<BTreeMap<String, BTreeMap<String, Alias>>, Box<dyn Error>> {
+    if !aliases_file.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let contents = std::fs::read_to_string(aliases_file)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+/// Add the ID to labels shared by more than one option, like schedules of the same name on
+/// different teams, so they can be told apart.
+fn disambiguate<T>(options: &mut [(String, &T)], id_of: fn(&T) -> &String) {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (label, _) in options.iter() {
+        *counts.entry(label.clone()).or_default() += 1;
+    }
+
+    for (label, option) in options.iter_mut() {
+        if counts[label.as_str()] > 1 {
+            label.push_str(&format!(" ({})", id_of(option)));
+        }
+    }
+}
+
+/// History of every profile, by profile name.
+fn load_all_history(history_file: &Path) -> Result<BTreeMap<String, History>, Box<dyn Error>> {
+    if !history_file.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let contents = std::fs::read_to_string(history_file)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+/// Forget the aliases and selection history of the profile `name`, so a profile added under that
+/// name later doesn't find IDs of another account.
+pub async fn forget_profile(name: &str) -> Result<(), Box<dyn Error>> {
+    forget_in(&paths::config_dir()?.join("aliases.json"), name)?;
+
+    let history_file = paths::state_dir()?.join("history.json");
+    if history_file.exists() {
+        let _lock = lock(&history_file).await?;
+        forget_in(&history_file, name)?;
+    }
+
+    Ok(())
+}
+
+/// Remove what `file`, which keeps something for every profile, keeps for the profile `name`.
+fn forget_in(file: &Path, name: &str) -> Result<(), Box<dyn Error>> {
+    if !file.exists() {
+        return Ok(());
+    }
+
+    let mut all: BTreeMap<String, Value> = serde_json::from_str(&std::fs::read_to_string(file)?)?;
+    if all.remove(name).is_some() {
+        write_atomically(file, serde_json::to_string_pretty(&all)?.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Replace `path` with `contents` so that readers, and the file after a crash, only ever see the
+/// old or the new contents. They are written to a temporary file that is renamed over `path`.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let tmp = with_suffix(path, &format!(".{}.tmp", std::process::id()));
+
+    let written = File::create(&tmp)
+        .and_then(|mut f| f.write_all(contents).and_then(|()| f.sync_all()))
+        .and_then(|()| std::fs::rename(&tmp, path));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    written?;
+
+    // the rename is only durable once the directory is synced too
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        File::open(dir)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+/// Take an exclusive lock next to `path` for reading and rewriting it, so concurrent runs don't
+/// fetch and write over each other. The lock is released when the returned file is dropped.
+async fn lock(path: &Path) -> Result<File, Box<dyn Error>> {
+    let lock_file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(with_suffix(path, ".lock"))?;
+
+    match lock_file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            eprintln!(
+                "waiting for another pd-quick-override to finish updating {}",
+                path.display()
+            );
+            // waiting blocks the thread, which the runtime needs for other tasks
+            let waiting = tokio::task::spawn_blocking(move || lock_file.lock().map(|()| lock_file));
+            return Ok(waiting.await??);
+        }
+        Err(TryLockError::Error(e)) => return Err(Box::from(e)),
+    }
+
+    Ok(lock_file)
+}
+
+/// Move a corrupt cache out of the way, keeping it around for a look at what went wrong.
+fn quarantine(storage_file: &Path) -> io::Result<PathBuf> {
+    let quarantined = with_suffix(
+        storage_file,
+        &format!(".corrupt-{}", Utc::now().timestamp()),
+    );
+    std::fs::rename(storage_file, &quarantined)?;
+
+    Ok(quarantined)
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut with_suffix = OsString::from(path);
+    with_suffix.push(suffix);
+
+    PathBuf::from(with_suffix)
+}
+
+/// Files from before the cache had a version are version 1.
+fn version_of(stored: &Value) -> u64 {
+    stored.get("version").and_then(Value::as_u64).unwrap_or(1)
+}
+
+/// Upgrade a stored cache, one version at a time, to the current format.
+fn migrate(mut stored: Value) -> Result<Value, NewerCache> {
+    loop {
+        match version_of(&stored) {
+            STORAGE_VERSION => return Ok(stored),
+            // version 1 caches were written before there were profiles, by the default profile
+            1 => {
+                if stored.get("profile").is_none_or(Value::is_null) {
+                    stored["profile"] = json!(DEFAULT_PROFILE);
+                }
+                stored["version"] = json!(2);
+            }
+            version => return Err(NewerCache(version)),
+        }
+    }
+}
+
+/// A cache in a format version this build doesn't know, written by a newer one.
+#[derive(Debug)]
+struct NewerCache(u64);
+
+impl Display for NewerCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "unknown cache format version {}, this build reads up to {STORAGE_VERSION}",
+            self.0
+        )
+    }
+}
+
+impl Error for NewerCache {}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::{
+        mockserver::{FakePagerduty, MockServer},
+        profile::Profile,
+        prompt::Scripted,
+    };
+
+    /// The database of `client`, loaded from a copy of `fake_storage.json` in a new directory.
+    async fn cached_db(client: &client::Client) -> (tempfile::TempDir, Database<'_>) {
+        let storage_dir = tempfile::tempdir().unwrap();
+        std::fs::copy("fake_storage.json", storage_dir.path().join("storage.json")).unwrap();
+        let db = Database::load_from(client, storage_dir.path().to_path_buf(), None, &[])
+            .await
+            .unwrap();
+
+        (storage_dir, db)
+    }
+
+    /// Like `cached_db`, with the schedules numbered `PS0`, `PS1`, ... in order. The schedules in
+    /// `fake_storage.json` share their IDs, which is no use for telling selections apart.
+    async fn cached_db_with_schedule_ids(
+        client: &client::Client,
+    ) -> (tempfile::TempDir, Database<'_>) {
+        let (storage_dir, mut db) = cached_db(client).await;
+        for (i, schedule) in db.storage.schedules.iter_mut().enumerate() {
+            schedule.id = format!("PS{i}");
+        }
+
+        (storage_dir, db)
+    }
+
+    #[tokio::test]
+    async fn test_loads_legacy_cache_for_default_profile() {
+        let mut fake = FakePagerduty::from_fixture();
+        fake.me["html_url"] = json!("https://acme.pagerduty.com/users/P12345");
+        let server = MockServer::start(fake).await;
+        let client = client::Client::with_base_url(String::from("test-key"), server.url.clone());
+        let (storage_dir, db) = cached_db(&client).await;
+
+        assert_eq!(db.storage.updated_at, 1677445731);
+        assert_eq!(db.storage.profile, "default");
+        assert_eq!(db.storage.account.as_deref(), Some("acme"));
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, vec!["/users/me"]);
+
+        let upgraded: Value = serde_json::from_str(
+            &std::fs::read_to_string(storage_dir.path().join("storage.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(upgraded["version"], json!(STORAGE_VERSION));
+        assert_eq!(upgraded["profile"], json!("default"));
+        assert_eq!(upgraded["account"], json!("acme"));
+    }
+
+    #[tokio::test]
+    async fn test_refetches_cache_of_another_account() {
+        let mut fake = FakePagerduty::from_fixture();
+        fake.me["html_url"] = json!("https://acme.pagerduty.com/users/P12345");
+        let server = MockServer::start(fake).await;
+        let client = client::Client::with_base_url(String::from("test-key"), server.url.clone());
+        let storage_dir = tempfile::tempdir().unwrap();
+        let mut cached: Value =
+            serde_json::from_str(&std::fs::read_to_string("fake_storage.json").unwrap()).unwrap();
+        cached["account"] = json!("initech");
+        std::fs::write(storage_dir.path().join("storage.json"), cached.to_string()).unwrap();
+
+        let db = Database::load_from(&client, storage_dir.path().to_path_buf(), None, &[])
+            .await
+            .unwrap();
+
+        assert_eq!(db.storage.account.as_deref(), Some("acme"));
+        assert_ne!(db.storage.updated_at, 1677445731);
+        assert!(server.requests().iter().any(|r| r.path == "/schedules"));
+    }
+
+    #[test]
+    fn test_forget_in() {
+        let dir = tempfile::tempdir().unwrap();
+        let aliases_file = dir.path().join("aliases.json");
+        std::fs::write(
+            &aliases_file,
+            r#"{"default": {"db": {"users": ["P1"]}}, "work": {"db": {"users": ["P2"]}}}"#,
+        )
+        .unwrap();
+
+        forget_in(&aliases_file, "work").unwrap();
+
+        let all = load_all_aliases(&aliases_file).unwrap();
+        assert_eq!(all.keys().collect::<Vec<_>>(), vec!["default"]);
+        assert!(forget_in(&dir.path().join("history.json"), "work").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_aliases() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = client::Client::with_base_url(String::from("test-key"), server.url.clone());
+        let (_storage_dir, mut db) = cached_db_with_schedule_ids(&client).await;
+
+        db.aliases.insert(
+            String::from("important"),
+            Alias::Schedules(vec![String::from("PS0"), String::from("PS1")]),
+        );
+        db.aliases.insert(
+            String::from("api"),
+            Alias::Schedules(vec![String::from("PS2")]),
+        );
+
+        let labels: Vec<String> = db
+            .schedule_options()
+            .into_iter()
+            .map(|(label, _)| label)
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                "@api [primary] unimportant schedule",
+                "[primary] important schedule",
+                "[primary] unimportant schedule",
+                "[secondary] important schedule",
+            ]
+        );
+
+        let mut prompt = Scripted {
+            selections: vec!["[secondary] important schedule"],
+            answers: vec![],
+        };
+        let picked = db
+            .pick_schedule(&mut prompt, Some("@important"))
+            .await
+            .unwrap();
+        assert_eq!(picked.id, "PS1");
+        let picked = db.pick_schedule(&mut prompt, Some("api")).await.unwrap();
+        assert_eq!(picked.id, "PS2");
+        let picked = db.pick_schedule(&mut prompt, Some("PS0")).await.unwrap();
+        assert_eq!(picked.name, "[primary] important schedule");
+        assert!(db.pick_schedule(&mut prompt, Some("nope")).await.is_err());
+        assert!(prompt.selections.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pick_schedule_among() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = client::Client::with_base_url(String::from("test-key"), server.url.clone());
+        let (_storage_dir, db) = cached_db_with_schedule_ids(&client).await;
+
+        let mut prompt = Scripted {
+            selections: vec!["[secondary] important schedule"],
+            answers: vec![],
+        };
+        let picked = db
+            .pick_schedule_among(
+                &mut prompt,
+                &[String::from("PS0"), String::from("important")],
+            )
+            .await
+            .unwrap();
+        assert_eq!(picked.id, "PS1");
+        assert!(prompt.selections.is_empty());
+
+        let picked = db
+            .pick_schedule_among(&mut prompt, &[String::from("unimportant")])
+            .await
+            .unwrap();
+        assert_eq!(picked.id, "PS2");
+        assert!(db
+            .pick_schedule_among(&mut prompt, &[String::from("nope")])
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_frecency() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = client::Client::with_base_url(String::from("test-key"), server.url.clone());
+        let (_storage_dir, mut db) = cached_db_with_schedule_ids(&client).await;
+
+        db.storage.schedules[0].teams = vec![client::Reference {
+            id: String::from("PT1"),
+            ..Default::default()
+        }];
+        db.my_teams = vec![String::from("PT1")];
+        let long_ago = Utc::now().timestamp() - 365 * 24 * 60 * 60;
+        db.history.borrow_mut().schedules.insert(
+            String::from("PS1"),
+            Usage {
+                count: 5,
+                last_used: long_ago,
+            },
+        );
+
+        let ids = |db: &Database<'_>| -> Vec<String> {
+            db.schedule_options()
+                .into_iter()
+                .map(|(_, s)| s.id.clone())
+                .collect()
+        };
+        assert_eq!(ids(&db), vec!["PS1", "PS0", "PS2"]);
+
+        // one selection today outweighs a few a year ago
+        let mut prompt = Scripted {
+            selections: vec!["[primary] unimportant schedule"],
+            answers: vec![],
+        };
+        db.pick_schedule(&mut prompt, None).await.unwrap();
+        assert_eq!(ids(&db), vec!["PS2", "PS1", "PS0"]);
+        assert_eq!(db.history.borrow().schedules["PS2"].count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_saves_history_under_lock() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = client::Client::with_base_url(String::from("test-key"), server.url.clone());
+        let (storage_dir, mut db) = cached_db(&client).await;
+        let history_file = storage_dir.path().join("state").join("history.json");
+        db.history_file = Some(history_file.clone());
+
+        let mut prompt = Scripted {
+            selections: vec!["[primary] unimportant schedule"],
+            answers: vec![],
+        };
+        db.pick_schedule(&mut prompt, None).await.unwrap();
+
+        let saved = load_all_history(&history_file).unwrap();
+        assert_eq!(saved["default"].schedules["P3456"].count, 1);
+        assert!(with_suffix(&history_file, ".lock").exists());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_is_not_recorded() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = client::Client::with_base_url(String::from("test-key"), server.url.clone())
+            .with_dry_run(true);
+        let (_storage_dir, db) = cached_db(&client).await;
+
+        let mut prompt = Scripted {
+            selections: vec!["[primary] unimportant schedule"],
+            answers: vec![],
+        };
+        db.pick_schedule(&mut prompt, None).await.unwrap();
+        assert!(db.history.borrow().schedules.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_team_filter() {
+        let mut fake = FakePagerduty::from_fixture();
+        for (i, user) in fake.users.iter_mut().enumerate() {
+            user["id"] = json!(format!("PU{i}"));
+        }
+        fake.users[1]["teams"] = json!([{ "id": "PT1", "summary": "Platform" }]);
+        fake.schedules[2]["teams"] = json!([{ "id": "PT1", "summary": "Platform" }]);
+        let server = MockServer::start(fake).await;
+        let client = client::Client::with_base_url(String::from("test-key"), server.url.clone());
+        let storage_dir = tempfile::tempdir().unwrap();
+        std::fs::copy("fake_storage.json", storage_dir.path().join("storage.json")).unwrap();
+        let platform = vec![String::from("PT1")];
+
+        // the legacy cache doesn't know any teams, so everything is fetched again
+        let db = Database::load_from(&client, storage_dir.path().to_path_buf(), None, &platform)
+            .await
+            .unwrap();
+        assert_eq!(db.storage.users.len(), 4);
+        assert_eq!(db.user_options().len(), 1);
+        assert_eq!(db.schedule_options().len(), 1);
+        let fetched = server.requests().len();
+        assert!(fetched > 0);
+
+        // the same cache does for any teams, and names still find everyone
+        let db = Database::load_from(&client, storage_dir.path().to_path_buf(), None, &platform)
+            .await
+            .unwrap();
+        assert_eq!(db.user_options()[0].1.id, "PU1");
+        assert_eq!(db.find_users("PU2").len(), 1);
+        let db = Database::load_from(&client, storage_dir.path().to_path_buf(), None, &[])
+            .await
+            .unwrap();
+        assert_eq!(db.user_options().len(), 4);
+        assert_eq!(server.requests().len(), fetched);
+    }
+
+    #[tokio::test]
+    async fn test_disambiguates_duplicate_names() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = client::Client::with_base_url(String::from("test-key"), server.url.clone());
+        let (_storage_dir, mut db) = cached_db_with_schedule_ids(&client).await;
+
+        for schedule in db.storage.schedules.iter_mut() {
+            schedule.name = String::from("[primary] important schedule");
+        }
+        db.storage.schedules[2].time_zone = Some(String::from("Europe/Paris"));
+
+        let labels: Vec<String> = db
+            .schedule_options()
+            .into_iter()
+            .map(|(label, _)| label)
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                "[primary] important schedule (Europe/Paris)",
+                "[primary] important schedule (PS0)",
+                "[primary] important schedule (PS1)",
+            ]
+        );
+
+        let mut prompt = Scripted {
+            selections: vec!["[primary] important schedule (PS1)"],
+            answers: vec![],
+        };
+        let picked = db.pick_schedule(&mut prompt, None).await.unwrap();
+        assert_eq!(picked.id, "PS1");
+    }
+
+    #[tokio::test]
+    async fn test_refetches_stale_cache() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = client::Client::with_base_url(String::from("test-key"), server.url.clone());
+        let storage_dir = tempfile::tempdir().unwrap();
+        std::fs::copy("fake_storage.json", storage_dir.path().join("storage.json")).unwrap();
+
+        let db = Database::load_from(
+            &client,
+            storage_dir.path().to_path_buf(),
+            Some(Duration::hours(24)),
+            &[],
+        )
+        .await
+        .unwrap();
+
+        assert_ne!(db.storage.updated_at, 1677445731);
+        assert!(!server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_refetches_unreadable_cache() {
+        for contents in [r#"{"users": 7}"#, r#"{"users": [{"#] {
+            let server = MockServer::start(FakePagerduty::from_fixture()).await;
+            let client =
+                client::Client::with_base_url(String::from("test-key"), server.url.clone());
+            let storage_dir = tempfile::tempdir().unwrap();
+            std::fs::write(storage_dir.path().join("storage.json"), contents).unwrap();
+
+            let db = Database::load_from(&client, storage_dir.path().to_path_buf(), None, &[])
+                .await
+                .unwrap();
+
+            assert_eq!(db.storage.users.len(), 4);
+            assert_eq!(db.storage.version, STORAGE_VERSION);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_keeps_cache_of_newer_version() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = client::Client::with_base_url(String::from("test-key"), server.url.clone());
+        let storage_dir = tempfile::tempdir().unwrap();
+        let newer = r#"{"version": 99, "users": []}"#;
+        std::fs::write(storage_dir.path().join("storage.json"), newer).unwrap();
+
+        let db = Database::load_from(&client, storage_dir.path().to_path_buf(), None, &[])
+            .await
+            .unwrap();
+
+        assert_eq!(db.storage.users.len(), 4);
+        assert_eq!(
+            std::fs::read_to_string(storage_dir.path().join("storage.json")).unwrap(),
+            newer
+        );
+        assert_eq!(std::fs::read_dir(storage_dir.path()).unwrap().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_quarantines_corrupt_cache() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = client::Client::with_base_url(String::from("test-key"), server.url.clone());
+        let storage_dir = tempfile::tempdir().unwrap();
+        std::fs::write(storage_dir.path().join("storage.json"), r#"{"users": [{"#).unwrap();
+
+        Database::load_from(&client, storage_dir.path().to_path_buf(), None, &[])
+            .await
+            .unwrap();
+
+        let mut files: Vec<String> = std::fs::read_dir(storage_dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(files.len(), 3, "{files:?}");
+        assert_eq!(files[0], "storage.json");
+        assert!(files[1].starts_with("storage.json.corrupt-"), "{files:?}");
+        assert_eq!(files[2], "storage.json.lock");
+        assert_eq!(
+            std::fs::read_to_string(storage_dir.path().join(&files[1])).unwrap(),
+            r#"{"users": [{"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refetches_cache_of_other_profile() {
+        let server = MockServer::start(FakePagerduty::from_fixture()).await;
+        let client = client::Client::with_base_url(String::from("test-key"), server.url.clone())
+            .with_profile(Profile::new("work"));
+        let storage_dir = tempfile::tempdir().unwrap();
+        std::fs::copy(
+            "fake_storage.json",
+            storage_dir.path().join("storage-work.json"),
+        )
+        .unwrap();
+
+        let db = Database::load_from(&client, storage_dir.path().to_path_buf(), None, &[])
+            .await
+            .unwrap();
+
+        assert_eq!(db.storage.profile, "work");
+        assert_ne!(db.storage.updated_at, 1677445731);
+        assert!(!server.requests().is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+//! The preview shown next to schedules while selecting one: who is on call and the next few
+//! shifts. The finder runs it again for every schedule highlighted, so what it shows is cached for
+//! a few minutes to keep scrolling through the list fast.
+
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+
+use crate::{
+    client::{Client, Schedule, ScheduleEntry},
+    paths, persistence,
+    table::format_time,
+};
+
+/// How long a preview is shown again before it is fetched anew.
+const FRESH_FOR: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// How far ahead shifts are shown.
+const DAYS_AHEAD: i64 = 7;
+
+const SHIFTS_SHOWN: usize = 5;
+
+/// Print the cached preview of the schedule with `schedule_id`, if there is a recent one. Checked
+/// before opening a client, which can mean a trip to the keyring or a token command.
+pub fn show_cached(profile: &str, schedule_id: &str, tz: Tz) -> bool {
+    let cached = paths::cache_dir()
+        .ok()
+        .and_then(|dir| fresh(&cache_file(&dir, profile, schedule_id, tz)));
+    match cached {
+        Some(preview) => {
+            print!("{preview}");
+            true
+        }
+        None => false,
+    }
+}
+
+/// Print the preview of the schedule with `schedule_id` and cache it.
+pub async fn run(client: &Client, schedule_id: &str, tz: Tz) {
+    let now = Utc::now().with_timezone(&tz);
+    let schedule = Schedule {
+        id: schedule_id.to_string(),
+        ..Default::default()
+    };
+    let entries = match client
+        .get_schedule_entries(&schedule, now, now + Duration::days(DAYS_AHEAD))
+        .await
+    {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!("could not load the schedule: {e}");
+            return;
+        }
+    };
+
+    let preview = render(&entries, now);
+    print!("{preview}");
+
+    // a preview that isn't cached is only slower
+    if let Ok(dir) = paths::cache_dir() {
+        let cache_file = cache_file(&dir, &client.profile().name, schedule_id, tz);
+        let _ = std::fs::create_dir_all(cache_file.parent().unwrap())
+            .and_then(|()| persistence::write_atomically(&cache_file, preview.as_bytes()));
+    }
+}
+
+/// Where the preview of a schedule is cached in `cache_dir`. Times are shown in `tz`, so previews
+/// in different time zones are cached apart.
+fn cache_file(cache_dir: &Path, profile: &str, schedule_id: &str, tz: Tz) -> PathBuf {
+    let zone = tz.name().replace('/', "_");
+    cache_dir
+        .join("previews")
+        .join(format!("{profile}-{schedule_id}-{zone}.txt"))
+}
+
+/// The contents of `cache_file` if it was written recently enough.
+fn fresh(cache_file: &Path) -> Option<String> {
+    let modified = std::fs::metadata(cache_file).ok()?.modified().ok()?;
+    let age = SystemTime::now().duration_since(modified).ok()?;
+    if age > FRESH_FOR {
+        return None;
+    }
+
+    std::fs::read_to_string(cache_file).ok()
+}
+
+fn render(entries: &[ScheduleEntry], now: DateTime<Tz>) -> String {
+    let tz = now.timezone();
+    let mut preview = String::new();
+
+    match entries.iter().find(|e| e.start <= now && now < e.end) {
+        Some(e) => preview.push_str(&format!(
+            "on call now: {}\n  until {}\n",
+            e.user.summary,
+            format_time(&e.end.with_timezone(&tz))
+        )),
+        None => preview.push_str("nobody is on call now\n"),
+    }
+
+    let upcoming: Vec<&ScheduleEntry> = entries
+        .iter()
+        .filter(|e| e.start > now)
+        .take(SHIFTS_SHOWN)
+        .collect();
+    if upcoming.is_empty() {
+        preview.push_str(&format!("\nno shifts in the next {DAYS_AHEAD} days\n"));
+        return preview;
+    }
+
+    preview.push_str("\nnext shifts:\n");
+    for e in upcoming {
+        preview.push_str(&format!(
+            "{}\n  {} until {}\n",
+            e.user.summary,
+            format_time(&e.start.with_timezone(&tz)),
+            format_time(&e.end.with_timezone(&tz))
+        ));
+    }
+
+    preview
+}
+
+#[cfg(test)]
+mod testing {
+    use chrono::{Offset, TimeZone};
+
+    use super::*;
+    use crate::client::Reference;
+
+    fn entry(user: &str, start: DateTime<Tz>, hours: i64) -> ScheduleEntry {
+        ScheduleEntry {
+            start: start.with_timezone(&start.offset().fix()),
+            end: (start + Duration::hours(hours)).with_timezone(&start.offset().fix()),
+            user: Reference {
+                id: String::from("P12345"),
+                summary: user.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let tz: Tz = "Europe/Paris".parse().unwrap();
+        let now = tz.with_ymd_and_hms(2024, 3, 4, 10, 0, 0).unwrap();
+        let entries = vec![
+            entry("Jane Doe", now - Duration::hours(2), 8),
+            entry("John Roe", now + Duration::hours(6), 12),
+        ];
+
+        assert_eq!(
+            render(&entries, now),
+            "on call now: Jane Doe\n  until Mon 2024-03-04 16:00 CET\n\n\
+             next shifts:\nJohn Roe\n  Mon 2024-03-04 16:00 CET until Tue 2024-03-05 04:00 CET\n"
+        );
+        assert_eq!(
+            render(&[], now),
+            "nobody is on call now\n\nno shifts in the next 7 days\n"
+        );
+    }
+
+    #[test]
+    fn test_cache_file() {
+        let dir = Path::new("/cache");
+        let tz: Tz = "America/New_York".parse().unwrap();
+
+        assert_eq!(
+            cache_file(dir, "work", "P3456", tz),
+            Path::new("/cache/previews/work-P3456-America_New_York.txt")
+        );
+        assert_ne!(
+            cache_file(dir, "work", "P3456", tz),
+            cache_file(dir, "work", "P3456", Tz::UTC)
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use std::{collections::BTreeMap, error::Error, fmt::Display, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{paths, persistence};
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// The pagerduty service region an account lives in. Each region has its own API host and API keys
+/// only work against the region of their account.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Region {
+    #[default]
+    Us,
+    Eu,
+}
+
+impl Region {
+    pub fn base_url(&self) -> &'static str {
+        match self {
+            Region::Us => "https://api.pagerduty.com",
+            Region::Eu => "https://api.eu.pagerduty.com",
+        }
+    }
+
+    pub fn other(&self) -> Region {
+        match self {
+            Region::Us => Region::Eu,
+            Region::Eu => Region::Us,
+        }
+    }
+}
+
+impl Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Region::Us => f.write_str("US"),
+            Region::Eu => f.write_str("EU"),
+        }
+    }
+}
+
+/// How a profile's token is sent to pagerduty. Classic REST API keys use the `Token` scheme, OAuth
+/// and scoped app tokens are bearer tokens.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthKind {
+    #[default]
+    ApiKey,
+    Oauth,
+}
+
+impl AuthKind {
+    pub fn other(&self) -> AuthKind {
+        match self {
+            AuthKind::ApiKey => AuthKind::Oauth,
+            AuthKind::Oauth => AuthKind::ApiKey,
+        }
+    }
+}
+
+impl Display for AuthKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthKind::ApiKey => f.write_str("API key"),
+            AuthKind::Oauth => f.write_str("OAuth token"),
+        }
+    }
+}
+
+/// Settings that belong to one pagerduty account. Every profile has its own API key in the
+/// keyring and its own cache of users and schedules.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Profile {
+    #[serde(skip)]
+    pub name: String,
+    #[serde(default)]
+    pub region: Region,
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    #[serde(default)]
+    pub auth: AuthKind,
+    /// The email sent in the `From` header of writes, which pagerduty requires for account level
+    /// API keys. Looked up from the token's user when not set.
+    #[serde(default)]
+    pub from_email: Option<String>,
+    /// A command printing the API key, used instead of the keyring.
+    #[serde(default)]
+    pub token_command: Option<String>,
+    /// A file holding the API key, used instead of the keyring.
+    #[serde(default)]
+    pub token_file: Option<PathBuf>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile::new(DEFAULT_PROFILE)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Profiles {
+    profiles: BTreeMap<String, Profile>,
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Profile {
+        Profile {
+            name: name.to_string(),
+            region: Region::default(),
+            time_zone: None,
+            auth: AuthKind::default(),
+            from_email: None,
+            token_command: None,
+            token_file: None,
+        }
+    }
+
+    /// Profile names end up in file names and keyring entries, so only letters, digits, `-` and
+    /// `_` are allowed.
+    pub fn check_name(name: &str) -> Result<(), Box<dyn Error>> {
+        let allowed = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        if name.is_empty() || !name.chars().all(allowed) {
+            return Err(Box::from(format!(
+                "{name:?} can't be a profile name, use only letters, digits, - and _"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Load the profile called `name`. The default profile always exists, any other has to be
+    /// added first.
+    pub fn load(name: &str) -> Result<Profile, Box<dyn Error>> {
+        let profiles = Self::load_all()?;
+
+        match profiles.profiles.get(name) {
+            Some(profile) => Ok(Profile {
+                name: name.to_string(),
+                ..profile.clone()
+            }),
+            None if name == DEFAULT_PROFILE => Ok(Profile::default()),
+            None => Err(Box::from(format!(
+                "there is no profile named {name}, add it with `profile add {name}`"
+            ))),
+        }
+    }
+
+    pub fn list() -> Result<Vec<Profile>, Box<dyn Error>> {
+        let mut profiles = Self::load_all()?;
+        profiles
+            .profiles
+            .entry(DEFAULT_PROFILE.to_string())
+            .or_default();
+
+        Ok(profiles
+            .profiles
+            .into_iter()
+            .map(|(name, profile)| Profile { name, ..profile })
+            .collect())
+    }
+
+    pub fn exists(name: &str) -> Result<bool, Box<dyn Error>> {
+        Ok(name == DEFAULT_PROFILE || Self::load_all()?.profiles.contains_key(name))
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let mut profiles = Self::load_all()?;
+        profiles.profiles.insert(self.name.clone(), self.clone());
+
+        Self::save_all(&profiles)
+    }
+
+    /// Forget a profile, along with its API key, cached users and schedules, aliases and history.
+    pub async fn remove(&self) -> Result<(), Box<dyn Error>> {
+        match self.keyring_entry().delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(Box::from(e)),
+        }
+
+        self.clear_cache()?;
+        persistence::forget_profile(&self.name).await?;
+
+        let mut profiles = Self::load_all()?;
+        profiles.profiles.remove(&self.name);
+        Self::save_all(&profiles)
+    }
+
+    /// Forget the cached users and schedules, so they are fetched again with the next API key.
+    pub fn clear_cache(&self) -> Result<(), Box<dyn Error>> {
+        let storage_file = paths::cache_dir()?.join(self.storage_file_name());
+        if storage_file.exists() {
+            std::fs::remove_file(storage_file)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.name == DEFAULT_PROFILE
+    }
+
+    /// The default profile keeps the keyring entry and cache file from before there were profiles.
+    pub fn keyring_entry(&self) -> keyring::Entry {
+        if self.is_default() {
+            keyring::Entry::new("pd-fast-override", "api-key")
+        } else {
+            keyring::Entry::new("pd-fast-override", &format!("api-key:{}", self.name))
+        }
+    }
+
+    pub fn storage_file_name(&self) -> String {
+        if self.is_default() {
+            String::from("storage.json")
+        } else {
+            format!("storage-{}.json", self.name)
+        }
+    }
+
+    fn load_all() -> Result<Profiles, Box<dyn Error>> {
+        let profiles_file = Self::get_profiles_file()?;
+        if !profiles_file.exists() {
+            return Ok(Profiles::default());
+        }
+
+        let contents = std::fs::read_to_string(profiles_file)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    fn save_all(profiles: &Profiles) -> Result<(), Box<dyn Error>> {
+        std::fs::create_dir_all(paths::config_dir()?)?;
+        persistence::write_atomically(
+            &Self::get_profiles_file()?,
+            serde_json::to_string(profiles)?.as_bytes(),
+        )?;
+
+        Ok(())
+    }
+
+    fn get_profiles_file() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(paths::config_dir()?.join("profiles.json"))
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn test_check_name() {
+        assert!(Profile::check_name("work-eu_2").is_ok());
+        assert!(Profile::check_name("").is_err());
+        assert!(Profile::check_name("../work").is_err());
+        assert!(Profile::check_name("work eu").is_err());
+        assert!(Profile::check_name("wörk").is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use std::io::{self, Write};
+
+use crate::fuzzyselect::{self, Preview, Selector};
+
+/// How commands ask the person running them to pick between things.
+pub trait Prompt {
+    /// Select one of `options`, which are offered by their label in the order given.
+    fn select<'a, T>(&mut self, options: &[(String, &'a T)]) -> io::Result<&'a T>;
+
+    /// Like `select`, previewing the highlighted option by its `key` where the prompt can.
+    fn select_previewed<'a, T>(
+        &mut self,
+        options: &[(String, &'a T)],
+        _key: fn(&T) -> &str,
+    ) -> io::Result<&'a T> {
+        self.select(options)
+    }
+
+    fn choose<'a>(&mut self, options: &[&'a str]) -> &'a str;
+
+    fn confirm(&mut self) -> bool {
+        self.choose(&["yes", "no"]) == "yes"
+    }
+
+    /// Ask `question` until something is answered.
+    fn ask(&mut self, question: &str) -> String;
+}
+
+/// Prompts on the terminal, selecting with `selector`.
+#[derive(Default)]
+pub struct Terminal {
+    pub selector: Selector,
+    /// Answer yes to every confirmation, other questions are still asked.
+    pub skip_confirm: bool,
+    /// The shell command that previews an option, given its key. There are no previews without.
+    pub preview_command: Option<String>,
+}
+
+impl Prompt for Terminal {
+    fn select<'a, T>(&mut self, options: &[(String, &'a T)]) -> io::Result<&'a T> {
+        fuzzyselect::select(self.selector, options, None)
+    }
+
+    fn select_previewed<'a, T>(
+        &mut self,
+        options: &[(String, &'a T)],
+        key: fn(&T) -> &str,
+    ) -> io::Result<&'a T> {
+        let preview = self
+            .preview_command
+            .as_deref()
+            .map(|command| Preview { command, key });
+        fuzzyselect::select(self.selector, options, preview.as_ref())
+    }
+
+    fn choose<'a>(&mut self, options: &[&'a str]) -> &'a str {
+        choose(options)
+    }
+
+    fn ask(&mut self, question: &str) -> String {
+        ask(question)
+    }
+
+    fn confirm(&mut self) -> bool {
+        if self.skip_confirm {
+            println!("confirmed, skip_confirm is set");
+            return true;
+        }
+
+        self.choose(&["yes", "no"]) == "yes"
+    }
+}
+
+/// Ask until one of `options` is given, either in full or by its first letter.
+pub fn choose<'a>(options: &[&'a str]) -> &'a str {
+    let prompt: Vec<String> = options
+        .iter()
+        .map(|o| format!("{}({})", &o[..1], &o[1..]))
+        .collect();
+
+    let sin = io::stdin();
+    let mut answer = String::new();
+    loop {
+        print!("{}? ", prompt.join("/"));
+        io::stdout().flush().expect("could not flush stdout");
+
+        answer.clear();
+        sin.read_line(&mut answer).expect("could not read stdin");
+
+        let trimmed = answer.trim();
+        if let Some(option) = options
+            .iter()
+            .find(|o| trimmed == **o || trimmed == &o[..1])
+        {
+            return option;
+        }
+
+        println!("got {}", trimmed);
+    }
+}
+
+/// Ask `question` until a non-empty answer is given.
+pub fn ask(question: &str) -> String {
+    let sin = io::stdin();
+    let mut answer = String::new();
+    loop {
+        print!("{question}: ");
+        io::stdout().flush().expect("could not flush stdout");
+
+        answer.clear();
+        sin.read_line(&mut answer).expect("could not read stdin");
+
+        let trimmed = answer.trim();
+        if !trimmed.is_empty() {
+            return trimmed.to_string();
+        }
+    }
+}
+
+/// A prompt that plays back canned answers, for tests.
+#[cfg(test)]
+pub struct Scripted {
+    pub selections: Vec<&'static str>,
+    pub answers: Vec<&'static str>,
+}
+
+#[cfg(test)]
+impl Prompt for Scripted {
+    fn select<'a, T>(&mut self, options: &[(String, &'a T)]) -> io::Result<&'a T> {
+        let key = self.selections.remove(0);
+        Ok(options
+            .iter()
+            .find(|(label, _)| label == key)
+            .unwrap_or_else(|| panic!("{key} is not an option"))
+            .1)
+    }
+
+    fn choose<'a>(&mut self, options: &[&'a str]) -> &'a str {
+        let answer = self.answers.remove(0);
+        options
+            .iter()
+            .find(|o| **o == answer)
+            .unwrap_or_else(|| panic!("{answer} is not an option"))
+    }
+
+    fn ask(&mut self, _question: &str) -> String {
+        self.answers.remove(0).to_string()
+    }
+}
@@ -0,0 +1,260 @@
+use chrono::{DateTime, Duration, FixedOffset, TimeZone};
+use chrono_tz::Tz;
+
+use crate::{
+    client::{Client, OverrideResult, Schedule, ScheduleEntry, ScheduleOverride, Sent, User},
+    persistence::Database,
+    prompt::Prompt,
+    table::format_time,
+};
+
+/// How many days before and after the days looked at shifts are loaded, so shifts that are already
+/// running or end after them are swapped whole rather than cut off at either end.
+const SHIFT_MARGIN_DAYS: i64 = 14;
+
+/// Swap one upcoming shift between two users of a schedule. Both overrides are created in a single
+/// request; if only one of them goes through it is deleted again so the schedule is never left
+/// half-swapped.
+pub async fn run(
+    client: &Client,
+    db: &Database<'_>,
+    prompt: &mut impl Prompt,
+    tz: Tz,
+    days: i64,
+    schedule: Option<&str>,
+) {
+    if schedule.is_none() {
+        println!("select the schedule to swap shifts on");
+    }
+    let schedule = db
+        .pick_schedule(prompt, schedule)
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        });
+    println!("select the first person");
+    let first = db.pick_user(prompt, None).await.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    });
+    println!("select the second person");
+    let second = db.pick_user(prompt, None).await.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    });
+
+    if first.id == second.id {
+        eprintln!("cannot swap {first} with themselves");
+        std::process::exit(1);
+    }
+
+    let now = tz.timestamp_opt(chrono::Utc::now().timestamp(), 0).unwrap();
+    let until = now + Duration::days(days);
+    let loaded = Loaded {
+        since: now - Duration::days(SHIFT_MARGIN_DAYS),
+        until: until + Duration::days(SHIFT_MARGIN_DAYS),
+    };
+    let entries: Vec<ScheduleEntry> = client
+        .get_schedule_entries(schedule, loaded.since, loaded.until)
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("could not load schedule: {e}");
+            std::process::exit(1);
+        })
+        .into_iter()
+        .filter(|e| e.end > now && e.start < until)
+        .collect();
+
+    let first_shift = select_shift(prompt, &entries, first, tz, days, &loaded);
+    let second_shift = select_shift(prompt, &entries, second, tz, days, &loaded);
+
+    println!("before:");
+    println!(
+        "\t{first} is on call {}",
+        format_shift(first_shift, tz, &loaded)
+    );
+    println!(
+        "\t{second} is on call {}",
+        format_shift(second_shift, tz, &loaded)
+    );
+    println!("after:");
+    println!("\t{second} covers {}", format_swapped(first_shift, now, tz));
+    println!("\t{first} covers {}", format_swapped(second_shift, now, tz));
+    println!("will create both overrides on schedule {schedule}, confirm to continue.");
+
+    if !prompt.confirm() {
+        return;
+    }
+
+    let overrides = vec![
+        ScheduleOverride::new(second, swapped_from(first_shift, now), first_shift.end),
+        ScheduleOverride::new(first, swapped_from(second_shift, now), second_shift.end),
+    ];
+
+    let sent = client
+        .create_schedule_overrides(schedule, overrides)
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("could not create overrides: {e}");
+            std::process::exit(1);
+        });
+    let results = match sent {
+        Sent::Done(results) => results,
+        Sent::DryRun => {
+            println!("dry run, no shifts were swapped");
+            return;
+        }
+    };
+
+    if results.len() == 2 && results.iter().all(|r| r.created.is_some()) {
+        println!("Shifts swapped! ");
+        return;
+    }
+
+    rollback(client, schedule, &results).await;
+    std::process::exit(1);
+}
+
+/// The time range schedule entries were loaded for. Pagerduty cuts entries off at its ends.
+struct Loaded {
+    since: DateTime<Tz>,
+    until: DateTime<Tz>,
+}
+
+fn select_shift<'a>(
+    prompt: &mut impl Prompt,
+    entries: &'a [ScheduleEntry],
+    user: &User,
+    tz: Tz,
+    days: i64,
+    loaded: &Loaded,
+) -> &'a ScheduleEntry {
+    let shifts: Vec<(String, &ScheduleEntry)> = entries
+        .iter()
+        .filter(|e| e.user.id == user.id)
+        .map(|e| (format_shift(e, tz, loaded), e))
+        .collect();
+
+    if shifts.is_empty() {
+        eprintln!("{user} has no shifts in the next {days} days");
+        std::process::exit(1);
+    }
+
+    println!("select the shift of {user} to swap");
+    prompt.select(&shifts).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    })
+}
+
+/// The whole shift, as far as it was loaded.
+fn format_shift(entry: &ScheduleEntry, tz: Tz, loaded: &Loaded) -> String {
+    let since = if entry.start <= loaded.since {
+        "before "
+    } else {
+        ""
+    };
+    let until = if entry.end >= loaded.until {
+        "after "
+    } else {
+        ""
+    };
+    format!(
+        "from {since}{} to {until}{}",
+        format_time(&entry.start.with_timezone(&tz)),
+        format_time(&entry.end.with_timezone(&tz))
+    )
+}
+
+/// Where the swap of a shift starts. The part of a running shift that has passed is left as it was.
+fn swapped_from(entry: &ScheduleEntry, now: DateTime<Tz>) -> DateTime<FixedOffset> {
+    entry.start.max(now.with_timezone(&entry.start.timezone()))
+}
+
+/// The part of the shift that is swapped, which is what is left of it when it is already running.
+fn format_swapped(entry: &ScheduleEntry, now: DateTime<Tz>, tz: Tz) -> String {
+    if entry.start >= now {
+        return format!(
+            "from {} to {}",
+            format_time(&entry.start.with_timezone(&tz)),
+            format_time(&entry.end.with_timezone(&tz))
+        );
+    }
+
+    format!(
+        "the rest of the running shift, from now to {}",
+        format_time(&entry.end.with_timezone(&tz))
+    )
+}
+
+async fn rollback(client: &Client, schedule: &Schedule, results: &[OverrideResult]) {
+    for result in results.iter().filter(|r| r.created.is_none()) {
+        eprintln!(
+            "pagerduty rejected an override (status {}): {}",
+            result.status,
+            result.errors.join(", ")
+        );
+    }
+
+    for created in results.iter().filter_map(|r| r.created.as_ref()) {
+        match client.delete_schedule_override(schedule, &created.id).await {
+            Ok(()) => eprintln!(
+                "rolled back override of {} from {} to {}",
+                created.user.summary, created.start, created.end
+            ),
+            Err(e) => eprintln!(
+                "could not roll back override {}, remove it by hand: {:?}",
+                created.id, e
+            ),
+        }
+    }
+
+    eprintln!("shifts were not swapped");
+}
+
+#[cfg(test)]
+mod testing {
+    use chrono::Offset;
+
+    use super::*;
+    use crate::client::Reference;
+
+    fn entry(start: DateTime<Tz>, hours: i64) -> ScheduleEntry {
+        ScheduleEntry {
+            start: start.with_timezone(&start.offset().fix()),
+            end: (start + Duration::hours(hours)).with_timezone(&start.offset().fix()),
+            user: Reference::default(),
+        }
+    }
+
+    #[test]
+    fn test_running_and_cut_off_shifts() {
+        let tz = Tz::UTC;
+        let now = tz.with_ymd_and_hms(2024, 3, 4, 10, 0, 0).unwrap();
+        let loaded = Loaded {
+            since: now - Duration::days(1),
+            until: now + Duration::days(1),
+        };
+
+        let running = entry(now - Duration::hours(2), 8);
+        assert_eq!(swapped_from(&running, now), now);
+        assert_eq!(
+            format_swapped(&running, now, tz),
+            "the rest of the running shift, from now to Mon 2024-03-04 16:00 UTC"
+        );
+
+        let upcoming = entry(now + Duration::hours(6), 12);
+        assert_eq!(swapped_from(&upcoming, now), upcoming.start);
+        assert_eq!(
+            format_shift(&upcoming, tz, &loaded),
+            "from Mon 2024-03-04 16:00 UTC to Tue 2024-03-05 04:00 UTC"
+        );
+
+        let cut_off = entry(now + Duration::hours(12), 12);
+        assert_eq!(
+            format_shift(&cut_off, tz, &loaded),
+            "from Mon 2024-03-04 22:00 UTC to after Tue 2024-03-05 10:00 UTC"
+        );
+    }
+}
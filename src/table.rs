@@ -0,0 +1,64 @@
+use chrono::{DateTime, Duration};
+use chrono_tz::Tz;
+
+/// Print rows as left aligned columns, each column as wide as its widest cell.
+pub fn print(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+
+    let headers: Vec<String> = headers.iter().map(|h| h.to_uppercase()).collect();
+    println!("{}", format_row(&headers, &widths));
+    for row in rows {
+        println!("{}", format_row(row, &widths));
+    }
+}
+
+fn format_row(cells: &[String], widths: &[usize]) -> String {
+    let padded: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{cell:width$}"))
+        .collect();
+
+    padded.join("  ").trim_end().to_string()
+}
+
+pub fn format_time(t: &DateTime<Tz>) -> String {
+    t.format("%a %Y-%m-%d %H:%M %Z").to_string()
+}
+
+/// A duration like "4h 30m" or "2d 1h", leaving out the parts that are zero.
+pub fn format_duration(d: Duration) -> String {
+    let parts = [
+        (d.num_days(), "d"),
+        (d.num_hours() % 24, "h"),
+        (d.num_minutes() % 60, "m"),
+    ];
+    let formatted: Vec<String> = parts
+        .iter()
+        .filter(|(n, _)| *n != 0)
+        .map(|(n, unit)| format!("{n}{unit}"))
+        .collect();
+
+    if formatted.is_empty() {
+        return String::from("0m");
+    }
+    formatted.join(" ")
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::minutes(270)), "4h 30m");
+        assert_eq!(format_duration(Duration::hours(49)), "2d 1h");
+        assert_eq!(format_duration(Duration::minutes(45)), "45m");
+        assert_eq!(format_duration(Duration::seconds(20)), "0m");
+    }
+}
@@ -0,0 +1,301 @@
+//! A picture of a schedule around a new override, drawn before it is created: who is on call, the
+//! overrides already there and the new one, a row each, under hour marks in the requester's and
+//! the schedule's time zone. Seeing 16:00 on one and 22:00 on the other makes a mixed up AM/PM or
+//! time zone hard to miss.
+
+use chrono::{DateTime, Duration, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+
+use crate::client::{Override, ScheduleEntry};
+
+/// Every column of a day is this many minutes.
+const MINUTES_PER_COLUMN: i64 = 30;
+
+/// Hour marks are put every this many hours.
+const HOURS_PER_MARK: u32 = 3;
+
+/// Longer overrides only have their first days drawn.
+const MAX_DAYS: usize = 7;
+
+/// Rows are labelled with at most this many characters.
+const MAX_LABEL: usize = 28;
+
+const ON_CALL: char = '=';
+const OVERRIDDEN: char = '~';
+const PROPOSED: char = '#';
+const EMPTY: char = '.';
+
+type Span = (DateTime<Utc>, DateTime<Utc>);
+
+/// The override about to be created.
+pub struct Proposed<'a> {
+    pub who: &'a str,
+    pub from: DateTime<Tz>,
+    pub to: DateTime<Tz>,
+}
+
+/// The days drawn for `proposed`, from the midnight before it starts to the midnight after it
+/// ends in its time zone.
+pub fn window(proposed: &Proposed<'_>) -> (DateTime<Tz>, DateTime<Tz>) {
+    let days = days(proposed);
+    let start = days[0].0;
+    let end = days[days.len() - 1].1;
+
+    (start, end)
+}
+
+/// Draw `entries` of the final schedule and the `overrides` already on it around `proposed`.
+pub fn render(
+    schedule_tz: Option<Tz>,
+    entries: &[ScheduleEntry],
+    overrides: &[Override],
+    proposed: &Proposed<'_>,
+) -> String {
+    let tz = proposed.from.timezone();
+    let mut zones = vec![tz];
+    if let Some(schedule_tz) = schedule_tz.filter(|s| s.name() != tz.name()) {
+        zones.push(schedule_tz);
+    }
+
+    // one row for everyone on call, whatever the number of shifts they have
+    let mut on_call: Vec<(String, Vec<Span>)> = Vec::new();
+    for e in entries {
+        let span = (e.start.with_timezone(&Utc), e.end.with_timezone(&Utc));
+        match on_call.iter_mut().find(|(user, _)| *user == e.user.summary) {
+            Some((_, spans)) => spans.push(span),
+            None => on_call.push((e.user.summary.clone(), vec![span])),
+        }
+    }
+    let mut bars: Vec<(String, Vec<Span>, char)> = on_call
+        .into_iter()
+        .map(|(user, spans)| (user, spans, ON_CALL))
+        .collect();
+    for o in overrides {
+        bars.push((
+            format!("override: {}", o.user.summary),
+            vec![(o.start.with_timezone(&Utc), o.end.with_timezone(&Utc))],
+            OVERRIDDEN,
+        ));
+    }
+    bars.push((
+        format!("new: {}", proposed.who),
+        vec![(
+            proposed.from.with_timezone(&Utc),
+            proposed.to.with_timezone(&Utc),
+        )],
+        PROPOSED,
+    ));
+
+    let mut drawing = String::new();
+    let days = days(proposed);
+    for (day_start, day_end) in days.iter().take(MAX_DAYS) {
+        let columns = ((*day_end - *day_start).num_minutes() / MINUTES_PER_COLUMN) as usize;
+
+        let mut rows: Vec<(String, String)> = zones
+            .iter()
+            .map(|zone| (zone.name().to_string(), marks(*day_start, columns, zone)))
+            .collect();
+        // leave out whoever has nothing on this day
+        rows.extend(
+            bars.iter()
+                .map(|(label, spans, fill)| (label.clone(), bar(*day_start, columns, spans, *fill)))
+                .filter(|(_, row)| row.contains(|c| c != EMPTY)),
+        );
+
+        drawing.push_str(&day_start.format("%a %Y-%m-%d\n").to_string());
+        for (label, row) in rows {
+            let label: String = label.chars().take(MAX_LABEL).collect();
+            drawing.push_str(format!("{label:MAX_LABEL$}  {row}").trim_end());
+            drawing.push('\n');
+        }
+        drawing.push('\n');
+    }
+
+    if days.len() > MAX_DAYS {
+        drawing.push_str(&format!(
+            "(only the first {MAX_DAYS} of {} days are shown)\n",
+            days.len()
+        ));
+    }
+    drawing.push_str(&format!(
+        "{ON_CALL} on call  {OVERRIDDEN} existing override  {PROPOSED} new override, \
+         one column is {MINUTES_PER_COLUMN} minutes\n"
+    ));
+
+    drawing
+}
+
+/// The start and end of every day `proposed` is on, in its time zone.
+fn days(proposed: &Proposed<'_>) -> Vec<(DateTime<Tz>, DateTime<Tz>)> {
+    let tz = proposed.from.timezone();
+    // where clocks skip midnight for daylight saving time, like in Chile, days start at the first
+    // minute that exists
+    let midnight = |date: chrono::NaiveDate| {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+        (0..24 * 60)
+            .find_map(|m| {
+                tz.from_local_datetime(&(midnight + Duration::minutes(m)))
+                    .earliest()
+            })
+            .expect("every day has a first moment")
+    };
+
+    let mut days = Vec::new();
+    let mut date = proposed.from.date_naive();
+    loop {
+        let next = date.succ_opt().unwrap();
+        days.push((midnight(date), midnight(next)));
+        if midnight(next) >= proposed.to {
+            return days;
+        }
+        date = next;
+    }
+}
+
+/// The hour marks of a day, in `zone`.
+fn marks(day_start: DateTime<Tz>, columns: usize, zone: &Tz) -> String {
+    let mut row = vec![' '; columns];
+    for i in 0..columns {
+        let at = (day_start + Duration::minutes(i as i64 * MINUTES_PER_COLUMN)).with_timezone(zone);
+        let free = i + 2 <= columns && row[i.saturating_sub(1)..i + 2].iter().all(|c| *c == ' ');
+        if at.minute() == 0 && at.hour().is_multiple_of(HOURS_PER_MARK) && free {
+            let hour = format!("{:02}", at.hour());
+            for (j, c) in hour.chars().enumerate() {
+                row[i + j] = c;
+            }
+        }
+    }
+
+    row.into_iter().collect()
+}
+
+/// A day's columns, `fill` where any of `spans` covers part of them.
+fn bar(day_start: DateTime<Tz>, columns: usize, spans: &[Span], fill: char) -> String {
+    (0..columns)
+        .map(|i| {
+            let from = day_start + Duration::minutes(i as i64 * MINUTES_PER_COLUMN);
+            let to = from + Duration::minutes(MINUTES_PER_COLUMN);
+            if spans.iter().any(|(start, end)| *start < to && from < *end) {
+                fill
+            } else {
+                EMPTY
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod testing {
+    use chrono::Offset;
+
+    use super::*;
+    use crate::client::Reference;
+
+    fn reference(name: &str) -> Reference {
+        Reference {
+            id: String::from("P12345"),
+            summary: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let paris: Tz = "Europe/Paris".parse().unwrap();
+        let at = |h| {
+            let t = tz.with_ymd_and_hms(2023, 2, 11, h, 0, 0).unwrap();
+            t.with_timezone(&t.offset().fix())
+        };
+
+        let entries = vec![
+            ScheduleEntry {
+                start: at(0),
+                end: at(12),
+                user: reference("Jane Doe"),
+            },
+            ScheduleEntry {
+                start: at(12),
+                end: at(12) + Duration::hours(12),
+                user: reference("John Roe"),
+            },
+        ];
+        let overrides = vec![Override {
+            id: String::from("PO1"),
+            start: at(9),
+            end: at(10),
+            user: reference("Bob"),
+        }];
+        let proposed = Proposed {
+            who: "Real Human",
+            from: tz.with_ymd_and_hms(2023, 2, 11, 16, 0, 0).unwrap(),
+            to: tz.with_ymd_and_hms(2023, 2, 11, 17, 30, 0).unwrap(),
+        };
+
+        let expected = "\
+Sat 2023-02-11
+America/New_York              00    03    06    09    12    15    18    21
+Europe/Paris                  06    09    12    15    18    21    00    03
+Jane Doe                      ========================........................
+John Roe                      ........................========================
+override: Bob                 ..................~~............................
+new: Real Human               ................................###.............
+
+= on call  ~ existing override  # new override, one column is 30 minutes
+";
+        assert_eq!(
+            render(Some(paris), &entries, &overrides, &proposed),
+            expected
+        );
+        assert_eq!(
+            window(&proposed),
+            (
+                tz.with_ymd_and_hms(2023, 2, 11, 0, 0, 0).unwrap(),
+                tz.with_ymd_and_hms(2023, 2, 12, 0, 0, 0).unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_render_over_days() {
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let proposed = Proposed {
+            who: "Real Human",
+            from: tz.with_ymd_and_hms(2023, 2, 11, 22, 0, 0).unwrap(),
+            to: tz.with_ymd_and_hms(2023, 2, 13, 0, 0, 0).unwrap(),
+        };
+
+        let drawing = render(None, &[], &[], &proposed);
+        let saturday = format!(
+            "Sat 2023-02-11\n{:30}{}\n",
+            "America/New_York", "00    03    06    09    12    15    18    21"
+        );
+        assert!(drawing.starts_with(&saturday), "{drawing}");
+        assert!(drawing.contains(&format!("{:30}{}####\n", "new: Real Human", ".".repeat(44))));
+        assert!(drawing.contains("\nSun 2023-02-12\n"));
+        assert!(drawing.contains(&format!("{:30}{}\n", "new: Real Human", "#".repeat(48))));
+    }
+
+    #[test]
+    fn test_window_when_midnight_is_skipped() {
+        // clocks in Santiago went from 23:59 on 2023-09-02 straight to 01:00 on 2023-09-03
+        let tz: Tz = "America/Santiago".parse().unwrap();
+        let proposed = Proposed {
+            who: "Real Human",
+            from: tz.with_ymd_and_hms(2023, 9, 3, 10, 0, 0).unwrap(),
+            to: tz.with_ymd_and_hms(2023, 9, 3, 12, 0, 0).unwrap(),
+        };
+
+        let (start, end) = window(&proposed);
+        assert_eq!(start, tz.with_ymd_and_hms(2023, 9, 3, 1, 0, 0).unwrap());
+        assert_eq!(end, tz.with_ymd_and_hms(2023, 9, 4, 0, 0, 0).unwrap());
+
+        let expected = "\
+Sun 2023-09-03
+America/Santiago                  03    06    09    12    15    18    21
+new: Real Human               ..................####........................
+
+= on call  ~ existing override  # new override, one column is 30 minutes
+";
+        assert_eq!(render(None, &[], &[], &proposed), expected);
+    }
+}
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use std::ops::Add;
 use thiserror::Error;
 
@@ -30,30 +31,92 @@ enum Meridiem {
     Pm,
 }
 
-pub const VALID_TIMES: [&str; 5] = [
+pub const VALID_TIMES: [&str; 6] = [
     "today, 10am-10pm",
+    "tomorrow, workday",
     "tomorrow, 10am-1pm",
     "today, 1pm - tomorrow, 8am",
     "10/1, 10:00am - 2:30pm",
     "10/1, 10AM - 10/2, 3PM",
 ];
 
-/// ranges come in the following forms:
+/// The hours the `workday` keyword stands for, as times like `9am` or `5:30pm`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Workday {
+    pub start: String,
+    pub end: String,
+}
+
+impl Default for Workday {
+    fn default() -> Self {
+        Workday {
+            start: String::from("9am"),
+            end: String::from("5pm"),
+        }
+    }
+}
+
+impl Workday {
+    /// Check both times parse, so a bad setting is caught when it is made.
+    pub fn validate(&self) -> Result<(), ParseError> {
+        let now = chrono_tz::UTC.timestamp_opt(0, 0).unwrap();
+        for time in [&self.start, &self.end] {
+            let lowered = time.to_lowercase();
+            parse_eol(parse_time(now, &lowered)?.rest)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub const VALID_INSTANTS: [&str; 4] = ["now", "4pm", "tomorrow, 9am", "10/1, 10:30am"];
+
+/// instants come in the following forms, see `parse` for <date> and <time>:
+/// ```
+/// <instant> := now
+///              | <time>
+///              | <date> , <time>
+/// ```
+pub fn parse_instant(now: &DateTime<Tz>, instant_str: &str) -> Result<DateTime<Tz>, ParseError> {
+    let lowered_string = instant_str.trim().to_lowercase();
+
+    if let Ok(now_parse) = parse_literal(&lowered_string, "now") {
+        parse_eol(now_parse.rest)?;
+        return Ok(*now);
+    }
+
+    if let Ok(date_parse) = parse_date(now, &lowered_string) {
+        let comma_parse = parse_literal(date_parse.rest, ",")?;
+        let time_parse = parse_time(date_parse.result, comma_parse.rest)?;
+        parse_eol(time_parse.rest)?;
+        return Ok(time_parse.result);
+    }
+
+    let today = now.duration_trunc(Duration::days(1)).unwrap();
+    let time_parse = parse_time(today, &lowered_string)?;
+    parse_eol(time_parse.rest)?;
+    Ok(time_parse.result)
+}
+
+/// ranges come in the following forms, where `workday` stands for the hours in `workday`:
 /// ```
 /// <full-range> := <date> , <time> - <time>
+///                 | <date> , workday
 ///                 | <date> <time> - <date> <time>
 ///                 | <date> - <date> , <time> - <time> /* TODO */
-
+///
 ///  <date> := today
 ///             | tomorrow
 ///             |  <D:month>/<D:day>
-
+///
 ///  <time> :=  <D:hour> (am | pm)
 ///             <D:hour>:<D:minute> (am | pm)
 /// ```
 pub fn parse(
     now: &DateTime<Tz>,
     range_str: &str,
+    workday: &Workday,
 ) -> Result<(DateTime<Tz>, DateTime<Tz>), ParseError> {
     let lowered_string = range_str.to_lowercase();
 
@@ -61,9 +124,28 @@ pub fn parse(
         return Ok((start, end));
     }
 
+    if let Ok((start, end)) = parse_workday_range(now, &lowered_string, workday) {
+        return Ok((start, end));
+    }
+
     parse_single_day_range(now, &lowered_string)
 }
 
+fn parse_workday_range(
+    now: &DateTime<Tz>,
+    source: &str,
+    workday: &Workday,
+) -> Result<(DateTime<Tz>, DateTime<Tz>), ParseError> {
+    let date_parse = parse_date(now, source)?;
+    let comma_parse = parse_literal(date_parse.rest, ",")?;
+    let workday_parse = parse_literal(comma_parse.rest, "workday")?;
+    parse_eol(workday_parse.rest)?;
+
+    let start = parse_time(date_parse.result, &workday.start.to_lowercase())?.result;
+    let end = parse_time(date_parse.result, &workday.end.to_lowercase())?.result;
+    Ok((start, end))
+}
+
 fn parse_single_day_range(
     now: &DateTime<Tz>,
     source: &str,
@@ -145,7 +227,7 @@ fn parse_month_day_date<'a>(
     })
 }
 
-fn parse_time(base: DateTime<Tz>, source: &str) -> Result<Parse<DateTime<Tz>>, ParseError> {
+fn parse_time(base: DateTime<Tz>, source: &str) -> Result<Parse<'_, DateTime<Tz>>, ParseError> {
     let hour_parse = parse_number(source)?;
     let mut rest = hour_parse.rest;
 
@@ -182,7 +264,7 @@ fn parse_time(base: DateTime<Tz>, source: &str) -> Result<Parse<DateTime<Tz>>, P
     Ok(Parse { rest, result: time })
 }
 
-fn parse_meridiem(source: &str) -> Result<Parse<Meridiem>, ParseError> {
+fn parse_meridiem(source: &str) -> Result<Parse<'_, Meridiem>, ParseError> {
     if let Ok(parse) = parse_literal(source, "am") {
         return Ok(Parse {
             rest: parse.rest,
@@ -200,7 +282,7 @@ fn parse_meridiem(source: &str) -> Result<Parse<Meridiem>, ParseError> {
     Err(ParseError::IllegalMeridiem(String::from(source)))
 }
 
-fn parse_number(source: &str) -> Result<Parse<u32>, ParseError> {
+fn parse_number(source: &str) -> Result<Parse<'_, u32>, ParseError> {
     let schars = source.chars().take_while(|x| x.is_numeric()).count();
     if schars == 0 {
         return Err(ParseError::ExpectedNumber(source.to_string()));
@@ -232,8 +314,8 @@ fn parse_literal<'a>(source: &'a str, literal: &str) -> Result<Parse<'a, ()>, Pa
     })
 }
 
-fn parse_eol(source: &str) -> Result<Parse<()>, ParseError> {
-    if source.len() == 0 {
+fn parse_eol(source: &str) -> Result<Parse<'_, ()>, ParseError> {
+    if source.is_empty() {
         Ok(Parse {
             rest: source,
             result: (),
@@ -275,8 +357,8 @@ mod testing {
 
         let run_test =
             |s: &str, from: LocalResult<DateTime<Utc>>, to: LocalResult<DateTime<Utc>>| {
-                let (parsed_from, parsed_to) =
-                    parse(&now, s).unwrap_or_else(|_| panic!("expected to parse {:?}", s));
+                let (parsed_from, parsed_to) = parse(&now, s, &Workday::default())
+                    .unwrap_or_else(|_| panic!("expected to parse {:?}", s));
                 assert_eq!(parsed_from.timestamp(), from.unwrap().timestamp());
                 assert_eq!(parsed_to.timestamp(), to.unwrap().timestamp());
             };
@@ -323,7 +405,62 @@ mod testing {
             "today, 10am - tomorrow, 2pm",
             Utc.with_ymd_and_hms(2023, 2, 11, 15, 0, 0),
             Utc.with_ymd_and_hms(2023, 2, 12, 19, 0, 0),
-        )
+        );
+
+        run_test(
+            "tomorrow, workday",
+            Utc.with_ymd_and_hms(2023, 2, 12, 14, 0, 0),
+            Utc.with_ymd_and_hms(2023, 2, 12, 22, 0, 0),
+        );
+    }
+
+    #[test]
+    fn test_custom_workday() {
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let now = tz.with_ymd_and_hms(2023, 2, 11, 12, 0, 0).unwrap();
+        let workday = Workday {
+            start: String::from("8:30AM"),
+            end: String::from("4pm"),
+        };
+
+        let (start, end) = parse(&now, "2/13, workday", &workday).unwrap();
+        assert_eq!(start, tz.with_ymd_and_hms(2023, 2, 13, 8, 30, 0).unwrap());
+        assert_eq!(end, tz.with_ymd_and_hms(2023, 2, 13, 16, 0, 0).unwrap());
+
+        assert!(workday.validate().is_ok());
+        assert!(Workday {
+            start: String::from("nine"),
+            ..Workday::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_instant() {
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let now = tz.with_ymd_and_hms(2023, 2, 11, 12, 0, 0).unwrap();
+
+        let run_test = |s: &str, expected: LocalResult<DateTime<Utc>>| {
+            let parsed =
+                parse_instant(&now, s).unwrap_or_else(|_| panic!("expected to parse {:?}", s));
+            assert_eq!(parsed.timestamp(), expected.unwrap().timestamp());
+        };
+
+        run_test("now", Utc.with_ymd_and_hms(2023, 2, 11, 17, 0, 0));
+        run_test("4pm", Utc.with_ymd_and_hms(2023, 2, 11, 21, 0, 0));
+        run_test(
+            "tomorrow, 9:30AM",
+            Utc.with_ymd_and_hms(2023, 2, 12, 14, 30, 0),
+        );
+        run_test("2/20, 10am", Utc.with_ymd_and_hms(2023, 2, 20, 15, 0, 0));
+
+        assert!(parse_instant(&now, "today").is_err());
+        assert!(parse_instant(&now, "now, 4pm").is_err());
+
+        for example in VALID_INSTANTS {
+            parse_instant(&now, example).expect("could not parse");
+        }
     }
 
     #[test]
@@ -332,7 +469,8 @@ mod testing {
 
         // UCT-5
         let now = tz.with_ymd_and_hms(2023, 3, 11, 12, 0, 0).unwrap();
-        let (start, end) = parse(&now, "today, 10pm - tomorrow, 10am").expect("expected to parse");
+        let (start, end) = parse(&now, "today, 10pm - tomorrow, 10am", &Workday::default())
+            .expect("expected to parse");
 
         let d = end - start;
         assert_eq!(d, Duration::hours(11));
@@ -360,7 +498,7 @@ mod testing {
         let now = tz.with_ymd_and_hms(2023, 2, 11, 12, 0, 0).unwrap();
 
         for example in VALID_TIMES {
-            parse(&now, example).expect("could not parse");
+            parse(&now, example, &Workday::default()).expect("could not parse");
         }
     }
 }
@@ -0,0 +1,201 @@
+use std::cmp::{max, min};
+
+use chrono::DateTime;
+use chrono_tz::Tz;
+
+use crate::{
+    client::{Client, Schedule, ScheduleOverride, Sent, User},
+    persistence::Database,
+    prompt::Prompt,
+    table::{self, format_time},
+};
+
+struct Shift<'a> {
+    schedule: Schedule,
+    start: DateTime<Tz>,
+    end: DateTime<Tz>,
+    cover: Option<&'a User>,
+}
+
+/// Find every shift the current user holds between `from` and `to` and hand each of them to
+/// someone else, either the `cover` user or whoever is picked per shift.
+pub async fn run(
+    client: &Client,
+    db: &Database<'_>,
+    prompt: &mut impl Prompt,
+    tz: Tz,
+    from: DateTime<Tz>,
+    to: DateTime<Tz>,
+    cover: Option<String>,
+) {
+    let me = client
+        .get_me()
+        .await
+        .expect("could not look up current user");
+    let oncalls = client
+        .get_oncalls(from, to, &[me.id.as_str()], &[])
+        .await
+        .expect("could not load on-call shifts");
+
+    let mut shifts: Vec<Shift> = Vec::new();
+    for oncall in oncalls {
+        let Some(schedule_ref) = oncall.schedule else {
+            eprintln!(
+                "you are on escalation policy {} directly, which can't be covered by an override",
+                oncall.escalation_policy.summary
+            );
+            continue;
+        };
+
+        let start = max(oncall.start.map_or(from, |s| s.with_timezone(&tz)), from);
+        let end = min(oncall.end.map_or(to, |e| e.with_timezone(&tz)), to);
+
+        // the same shift shows up once for every escalation policy that uses the schedule
+        if shifts
+            .iter()
+            .any(|s| s.schedule.id == schedule_ref.id && s.start == start && s.end == end)
+        {
+            continue;
+        }
+
+        let schedule = db
+            .storage
+            .schedules
+            .iter()
+            .find(|s| s.id == schedule_ref.id)
+            .cloned()
+            .unwrap_or(Schedule {
+                id: schedule_ref.id,
+                name: schedule_ref.summary,
+                ..Schedule::default()
+            });
+
+        shifts.push(Shift {
+            schedule,
+            start,
+            end,
+            cover: None,
+        });
+    }
+
+    if shifts.is_empty() {
+        println!("{me} has no shifts from {from} to {to}, enjoy your time off!");
+        return;
+    }
+    shifts.sort_by_key(|s| s.start);
+
+    // a group alias as the cover still asks which of the group covers each shift
+    let single_cover = cover
+        .as_deref()
+        .is_some_and(|c| db.find_users(c).len() == 1);
+
+    for shift in shifts.iter_mut() {
+        if !single_cover {
+            println!(
+                "select who covers {} from {} to {}",
+                shift.schedule,
+                format_time(&shift.start),
+                format_time(&shift.end)
+            );
+        }
+        let user = db
+            .pick_user(prompt, cover.as_deref())
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{e}");
+                std::process::exit(1);
+            });
+
+        if user.id == me.id {
+            eprintln!("{user} cannot cover their own shift");
+            std::process::exit(1);
+        }
+        shift.cover = Some(user);
+    }
+
+    table::print(&["schedule", "from", "to", "covered by"], &rows(&shifts));
+    println!(
+        "will create {} overrides, confirm to continue.",
+        shifts.len()
+    );
+    if !prompt.confirm() {
+        return;
+    }
+
+    let mut results = Vec::new();
+    let mut schedule_ids: Vec<&str> = Vec::new();
+    for shift in shifts.iter() {
+        if !schedule_ids.contains(&shift.schedule.id.as_str()) {
+            schedule_ids.push(&shift.schedule.id);
+        }
+    }
+    for schedule_id in schedule_ids {
+        let batch: Vec<&Shift> = shifts
+            .iter()
+            .filter(|s| s.schedule.id == schedule_id)
+            .collect();
+        let overrides = batch
+            .iter()
+            .map(|s| ScheduleOverride::new(s.cover.unwrap(), s.start, s.end))
+            .collect();
+
+        match client
+            .create_schedule_overrides(&batch[0].schedule, overrides)
+            .await
+        {
+            Ok(Sent::DryRun) => {}
+            Ok(Sent::Done(created)) => {
+                for (shift, result) in batch.into_iter().zip(created) {
+                    let status = if result.created.is_some() {
+                        String::from("created")
+                    } else {
+                        format!("failed: {}", result.errors.join(", "))
+                    };
+                    results.push((shift, status));
+                }
+            }
+            Err(e) => {
+                for shift in batch {
+                    results.push((shift, format!("failed: {e}")));
+                }
+            }
+        }
+    }
+
+    if client.dry_run() {
+        println!("dry run, no overrides were created");
+        return;
+    }
+
+    let mut failed = false;
+    let result_rows: Vec<Vec<String>> = results
+        .into_iter()
+        .map(|(shift, status)| {
+            failed |= status != "created";
+            let mut row = row(shift);
+            row.push(status);
+            row
+        })
+        .collect();
+    table::print(
+        &["schedule", "from", "to", "covered by", "result"],
+        &result_rows,
+    );
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn rows(shifts: &[Shift]) -> Vec<Vec<String>> {
+    shifts.iter().map(row).collect()
+}
+
+fn row(shift: &Shift) -> Vec<String> {
+    vec![
+        shift.schedule.name.clone(),
+        format_time(&shift.start),
+        format_time(&shift.end),
+        shift.cover.map_or(String::new(), |u| u.email.clone()),
+    ]
+}
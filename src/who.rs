@@ -0,0 +1,70 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Duration};
+use chrono_tz::Tz;
+
+use crate::{
+    client::{Client, Oncall},
+    persistence::Database,
+    table::{self, format_time},
+};
+
+/// Print who is on call at `at`, on the schedules matching `filters` (or every cached schedule).
+pub async fn run(client: &Client, db: &Database<'_>, tz: Tz, at: DateTime<Tz>, filters: &[String]) {
+    let until = at + Duration::minutes(1);
+
+    let mut oncalls = Vec::new();
+    if filters.is_empty() {
+        let known: HashSet<&str> = db.storage.schedules.iter().map(|s| s.id.as_str()).collect();
+        oncalls = client
+            .get_oncalls(at, until, &[], &[])
+            .await
+            .expect("could not load on-call shifts");
+        oncalls.retain(|o| {
+            o.schedule
+                .as_ref()
+                .is_some_and(|s| known.contains(s.id.as_str()))
+        });
+    } else {
+        let schedules = db.match_schedules(filters).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        });
+        let ids: Vec<&str> = schedules.iter().map(|s| s.id.as_str()).collect();
+
+        // keep the query string a reasonable length when a filter matches lots of schedules
+        for chunk in ids.chunks(100) {
+            let page = client
+                .get_oncalls(at, until, &[], chunk)
+                .await
+                .expect("could not load on-call shifts");
+            oncalls.extend(page);
+        }
+    }
+
+    let mut rows: Vec<Vec<String>> = oncalls.iter().map(|o| row(o, tz)).collect();
+    rows.sort();
+    rows.dedup();
+
+    if rows.is_empty() {
+        println!("nobody is on call at {}", format_time(&at));
+        return;
+    }
+
+    println!("on call at {}:", format_time(&at));
+    table::print(&["schedule", "level", "user", "until"], &rows);
+}
+
+fn row(oncall: &Oncall, tz: Tz) -> Vec<String> {
+    vec![
+        oncall
+            .schedule
+            .as_ref()
+            .map_or(String::new(), |s| s.summary.clone()),
+        oncall.escalation_level.to_string(),
+        oncall.user.summary.clone(),
+        oncall.end.map_or(String::from("permanent"), |e| {
+            format_time(&e.with_timezone(&tz))
+        }),
+    ]
+}